@@ -0,0 +1,183 @@
+use chrono::Utc;
+use lazy_static::lazy_static;
+use rocket::request::{FromRequest, Outcome, Request};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::data::ApiKey;
+use crate::database;
+use crate::exconfig::CONFIG;
+
+
+// Owner API methods available for public access.
+pub const OWNER_METHODS: [&str; 3] = ["get_connected_peers", "get_peers", "get_status"];
+
+
+// API key passed via 'X-API-Key' header.
+pub struct ApiKeyHeader<'r>(pub Option<&'r str>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiKeyHeader<'r> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(ApiKeyHeader(req.headers().get_one("X-API-Key")))
+    }
+}
+
+
+// Admin session length, in seconds.
+const SESSION_TTL: i64 = 24 * 3600;
+
+lazy_static! {
+    // Random session ids given by the login form, with their creation time.
+    static ref SESSIONS: Mutex<HashMap<String, i64>> = Mutex::new(HashMap::new());
+}
+
+
+// Admin access via 'X-Admin-Token' header or 'admin_session' cookie (set by the login form).
+pub struct AdminToken {
+    pub valid:     bool,
+    // A token or a session was passed, but it is not valid
+    pub attempted: bool,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminToken {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let (valid, attempted) = match (req.headers().get_one("X-Admin-Token"), req.cookies().get("admin_session")) {
+            (Some(token), _) => (is_admin(Some(token)), true),
+            (None, Some(id)) => (is_session(id.value()), true),
+            (None, None)     => (false, false),
+        };
+
+        Outcome::Success(AdminToken { valid, attempted: attempted && !valid })
+    }
+}
+
+
+// Checking the admin token, the admin pages are disabled if no token is configured.
+pub fn is_admin(token: Option<&str>) -> bool {
+    !CONFIG.admin_token.is_empty() && token == Some(CONFIG.admin_token.as_str())
+}
+
+
+// Starting an admin session, the cookie holds a random id instead of the token.
+pub fn new_session() -> String {
+    let id: String = rand::random::<[u8; 32]>().iter().map(|b| format!("{:02x}", b)).collect();
+    let now        = Utc::now().timestamp();
+    let mut list   = SESSIONS.lock().unwrap();
+
+    list.retain(|_, created| now - *created < SESSION_TTL);
+    list.insert(id.clone(), now);
+
+    id
+}
+
+fn is_session(id: &str) -> bool {
+    !CONFIG.admin_token.is_empty()
+        && SESSIONS.lock().unwrap().get(id).is_some_and(|created| Utc::now().timestamp() - created < SESSION_TTL)
+}
+
+
+// Loading API keys from the config and the database.
+pub fn load() -> Vec<ApiKey> {
+    if CONFIG.database.is_empty() {
+        return CONFIG.api_keys.clone();
+    }
+
+    let conn = database::open_db_connection(&CONFIG.database).expect("failed to open database");
+
+    database::create_api_keys_table(&conn).expect("failed to create api_keys table");
+
+    for key in CONFIG.api_keys.iter() {
+        database::save_api_key(&conn, key).expect("failed to save api key");
+    }
+
+    database::remove_config_api_keys(&conn, &CONFIG.api_keys).expect("failed to remove api keys");
+
+    database::read_api_keys(&conn).expect("failed to read api keys")
+}
+
+
+// Whether the method is available without an API key.
+pub fn is_public(method: &str, rpc_type: &str) -> bool {
+    if CONFIG.public_api != "enabled" {
+        return false;
+    }
+
+    rpc_type == "foreign" || OWNER_METHODS.contains(&method)
+}
+
+
+// Checking access to the method and counting key usage.
+pub fn authorize(keys: &Arc<Mutex<Vec<ApiKey>>>, key: Option<&str>, method: &str,
+                 rpc_type: &str) -> Result<(), &'static str> {
+    let key = match key {
+        Some(value) => value,
+        None => {
+            if is_public(method, rpc_type) {
+                return Ok(());
+            }
+
+            return Err("not allowed");
+        },
+    };
+
+    let mut keys = keys.lock().unwrap();
+
+    let entry = match keys.iter_mut().find(|k| k.key == key) {
+        Some(value) => value,
+        None => return Err("invalid api key"),
+    };
+
+    if !is_public(method, rpc_type) && !entry.methods.iter().any(|m| m == method || m == "*") {
+        return Err("not allowed");
+    }
+
+    let now    = Utc::now();
+    let window = now.timestamp() / 60;
+
+    if entry.window != window {
+        entry.window     = window;
+        entry.window_cnt = 0;
+    }
+
+    if entry.rate_limit > 0 && entry.window_cnt >= entry.rate_limit {
+        return Err("rate limit exceeded");
+    }
+
+    entry.window_cnt += 1;
+    entry.requests   += 1;
+    entry.last_used   = now.format("%Y-%m-%d %H:%M:%S").to_string();
+
+    Ok(())
+}
+
+
+// Hiding most of the key, e.g. for the admin page.
+pub fn mask(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+
+    if chars.len() > 8 {
+        format!("{}...{}", chars[..4].iter().collect::<String>(), chars[chars.len() - 4..].iter().collect::<String>())
+    } else {
+        "*".repeat(chars.len())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_keys() {
+        assert_eq!(mask("0123456789abcdef"), "0123...cdef");
+        assert_eq!(mask("short"), "*****");
+        assert_eq!(mask("ключ-доступа-123"), "ключ...-123");
+        assert_eq!(mask("ключключ"), "********");
+    }
+}
@@ -0,0 +1,239 @@
+use chrono::{NaiveDate, Utc};
+use country_emoji::code_to_flag;
+use rusqlite::Connection;
+use serde_json::Value;
+use std::net::SocketAddr;
+
+use crate::data::ConnectedNode;
+use crate::database;
+use crate::geoip;
+use crate::networks::Network;
+use crate::p2p;
+use crate::requests;
+
+
+// Peers that were not reachable for this long are forgotten, in seconds.
+const FORGET_AFTER: i64 = 30 * 24 * 3600;
+// Peers behind our node by more than this number of blocks are stale.
+const STALE_BLOCKS: u64 = 60;
+
+
+// Parsing get_peers or get_connected_peers result.
+fn parse_peers(resp: &Value) -> Vec<ConnectedNode> {
+    let mut peers = Vec::new();
+
+    if let Some(list) = resp["result"]["Ok"].as_array() {
+        for peer in list {
+            let mut node = ConnectedNode::new();
+
+            // Onion and other non-ip addresses can't be probed
+            match peer["addr"].as_str() {
+                Some(addr) if addr.parse::<SocketAddr>().is_ok() => node.address = addr.to_string(),
+                _                                                => continue,
+            }
+
+            node.user_agent  = peer["user_agent"].as_str().unwrap_or_default().to_string();
+            node.bits        = peer["capabilities"]["bits"].as_u64().unwrap_or_default() as u32;
+            node.is_archival = node.bits & (1 << 5) != 0;
+
+            peers.push(node);
+        }
+    }
+
+    peers
+}
+
+
+// Collecting peers known to our node (get_peers includes peers we are not connected to)
+// and to the stats source nodes.
+async fn collect(net: &Network) -> Vec<ConnectedNode> {
+    let mut peers = requests::get_conn_nodes(net);
+
+    match requests::call(net, "get_peers", "[null]", "1", "owner").await {
+        Ok(resp) => peers.extend(parse_peers(&resp)),
+        Err(e)   => warn!("{}", e),
+    }
+
+    for endpoint in net.config.stats_source.clone() {
+        match requests::call_external("get_peers", "[null]", "1", "owner", endpoint.clone()).await {
+            Ok(resp) => {
+                            if resp["result"]["Ok"].is_null() {
+                                // Not all nodes provide get_peers, fall back to connected peers
+                                if let Ok(resp) = requests::call_external("get_connected_peers", "[]", "1", "owner", endpoint).await {
+                                    peers.extend(parse_peers(&resp));
+                                }
+                            } else {
+                                peers.extend(parse_peers(&resp));
+                            }
+                        },
+            Err(e)   => warn!("{}", e),
+        }
+    }
+
+    peers
+}
+
+
+// Crawling the network: merging newly discovered peers into the peer list,
+// handshaking all known peers and recording the network size.
+pub async fn crawl(net: &Network) -> Result<(), anyhow::Error> {
+    let reported  = collect(net).await;
+    let mut peers = net.netstats.lock().unwrap().peers.clone();
+    let now       = Utc::now().timestamp();
+
+    for node in reported {
+        match peers.iter_mut().find(|p| p.address == node.address) {
+            Some(peer) => {
+                              if !node.user_agent.is_empty() {
+                                  peer.user_agent  = node.user_agent;
+                                  peer.bits        = node.bits;
+                                  peer.is_archival = node.is_archival;
+                              }
+                          },
+            None       => {
+                              let mut peer = node;
+
+                              peer.first_seen = now;
+                              peers.push(peer);
+                          },
+        }
+    }
+
+    let (chain, total_diff, block_diff) = {
+        let dash = net.dash.lock().unwrap();
+        (dash.chain.clone(), dash.total_diff, dash.difficulty.parse::<u64>().unwrap_or_default())
+    };
+    let other = if chain == "test" { "main" } else { "test" };
+
+    let results   = p2p::handshake_all(peers.iter().map(|p| p.address.clone()).collect(), &chain).await;
+    let mut retry = Vec::new();
+
+    for (addr, result) in results {
+        let peer = match peers.iter_mut().find(|p| p.address == addr) {
+            Some(peer) => peer,
+            None       => continue,
+        };
+
+        peer.probes   += 1;
+        peer.reachable = false;
+        peer.status    = String::new();
+
+        let shake = match result {
+            Ok(Some(shake)) => shake,
+            Ok(None)        => {
+                                   // Listening, but not on our network, retried with the other network magic
+                                   retry.push(addr);
+                                   continue;
+                               },
+            Err(_e)         => continue,
+        };
+
+        // Our magic, but another chain
+        if shake.genesis != p2p::genesis(&chain) {
+            peer.status  = "wrong-genesis".to_string();
+            peer.genesis = shake.genesis;
+            continue;
+        }
+
+        peer.reachable   = true;
+        peer.successes  += 1;
+        peer.last_seen   = now;
+        peer.user_agent  = shake.user_agent;
+        peer.bits        = shake.capabilities;
+        peer.is_archival = peer.bits & (1 << 5) != 0;
+        peer.version     = shake.version;
+        peer.difficulty  = shake.total_difficulty;
+        peer.genesis     = shake.genesis;
+
+        // More than an hour of work behind our node
+        if block_diff > 0 && peer.difficulty + STALE_BLOCKS * block_diff < total_diff {
+            peer.status = "stale".to_string();
+        }
+
+        if let Ok(socket_addr) = peer.address.parse::<SocketAddr>() {
+            if let Some(location) = geoip::lookup(socket_addr.ip()).await {
+                peer.location = location.country;
+                peer.isp      = location.isp;
+                peer.asn      = location.asn;
+                peer.flag     = code_to_flag(&location.country_code).unwrap_or_default().to_string();
+            }
+        }
+    }
+
+    for (addr, result) in p2p::handshake_all(retry, other).await {
+        if let Some(peer) = peers.iter_mut().find(|p| p.address == addr) {
+            peer.status = match result {
+                Ok(Some(_shake)) => format!("{}net", other),
+                _                => "non-grin".to_string(),
+            };
+        }
+    }
+
+    peers.retain(|p| now - p.last_seen.max(p.first_seen) < FORGET_AFTER);
+
+    let reach_nodes: Vec<ConnectedNode> = peers.iter().filter(|p| p.reachable).cloned().collect();
+
+    info!("crawled {} peers, {} reachable ({}).", peers.len(), reach_nodes.len(), net.config.name);
+
+    if !net.config.database.is_empty() {
+        let conn = database::open_db_connection(&net.config.database)?;
+
+        database::save_peers(&conn, &peers)?;
+        database::save_network_size(&conn, &Utc::now().format("%Y-%m-%d").to_string(), peers.len(), reach_nodes.len())?;
+
+        load_history(net, &conn)?;
+    }
+
+    let mut nstats = net.netstats.lock().unwrap();
+
+    nstats.reach_nodes = reach_nodes;
+    nstats.peers       = peers;
+    nstats.crawled     = now;
+
+    Ok(())
+}
+
+
+// Reading the peer list saved by the previous run.
+pub fn load(net: &Network, conn: &Connection) -> Result<(), anyhow::Error> {
+    let peers = database::read_peers(conn)?;
+    let mut nstats = net.netstats.lock().unwrap();
+
+    nstats.reach_nodes = peers.iter().filter(|p| p.reachable).cloned().collect();
+    nstats.peers       = peers;
+
+    Ok(())
+}
+
+
+// Aligning daily network size with the statistics dates.
+pub fn load_history(net: &Network, conn: &Connection) -> Result<(), anyhow::Error> {
+    let daily     = database::read_network_size(conn)?;
+    let mut stats = net.stats.lock().unwrap();
+
+    let mut known = Vec::new();
+    let mut reach = Vec::new();
+
+    for date in stats.date.iter() {
+        // Statistics dates are stored as "DD-MM-YYYY" strings
+        let date = NaiveDate::parse_from_str(date.trim_matches('"'), "%d-%m-%Y")
+                   .map(|d| d.format("%Y-%m-%d").to_string())
+                   .unwrap_or_default();
+
+        match daily.iter().find(|d| d.0 == date) {
+            Some((_, k, r)) => {
+                                   known.push(k.to_string());
+                                   reach.push(r.to_string());
+                               },
+            None            => {
+                                   known.push("null".to_string());
+                                   reach.push("null".to_string());
+                               },
+        }
+    }
+
+    stats.peers_known = known;
+    stats.peers_reach = reach;
+
+    Ok(())
+}
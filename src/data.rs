@@ -1,4 +1,6 @@
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::HashMap;
 
     
 // Weights
@@ -7,6 +9,10 @@ pub const INPUT_WEIGHT:  f64 = 1.0;
 pub const OUTPUT_WEIGHT: f64 = 21.0;
 
     
+// Coinbase outputs can be spent after this number of blocks
+pub const COINBASE_MATURITY: u64 = 1440;
+
+
 // Sizes in bytes
 pub const KERNEL_SIZE: u64 = 1 + 8 + 8 + 33 + 64;
 pub const INPUT_SIZE:  u64 = 1 + 33;
@@ -22,6 +28,10 @@ pub struct Dashboard {
     pub sync:            String,
     pub node_ver:        String,
     pub proto_ver:       String,
+    pub total_diff:      u64,
+    pub tip_hash:        String,
+    // tip header timestamp
+    pub tip_time:        i64,
     // connections
     pub inbound:         u16,
     pub outbound:        u16,
@@ -36,6 +46,9 @@ pub struct Dashboard {
     pub volume_btc:      String,
     pub cap_usd:         String,
     pub cap_btc:         String,
+    pub price_source:    String,
+    // fiat prices of 1 grin by currency code
+    pub fiat:            HashMap<String, f64>,
     // blockchain
     pub disk_usage:      String,
     // hashrate
@@ -63,6 +76,9 @@ impl Dashboard {
             sync:            String::new(),
             node_ver:        String::new(),
             proto_ver:       String::new(),
+            total_diff:      0,
+            tip_hash:        String::new(),
+            tip_time:        0,
             inbound:         0,
             outbound:        0,
             supply:          String::new(),
@@ -75,6 +91,8 @@ impl Dashboard {
             volume_btc:      String::new(),
             cap_usd:         String::new(),
             cap_btc:         String::new(),
+            price_source:    String::new(),
+            fiat:            HashMap::new(),
             disk_usage:      String::new(),
             hashrate:        String::new(),
             hashrate_kgs:    String::new(),
@@ -101,13 +119,22 @@ pub struct Block {
     pub weight:   f64,
     pub size:     String,
     pub fees:     f64,
-    pub kernels:  Vec<(String, String, String)>,
+    pub kernels:  Vec<(String, String, String, String)>,
     pub inputs:   Vec<String>,
     pub outputs:  Vec<(String,String)>,
     pub ker_len:  u64,
     pub in_len:   u64,
     pub out_len:  u64,
     pub raw_data: String,
+    // Block reward with fees (GRIN), coinbase output and kernel
+    pub reward:   f64,
+    pub cb_out:   String,
+    pub cb_ker:   String,
+    // Blocks until the coinbase output can be spent, 0 when mature
+    pub maturity: u64,
+    // Kernel sums check and the number of valid kernel signatures, None if not verified
+    pub sums_ok:  Option<bool>,
+    pub sigs_ok:  u64,
 }
 
 impl Block {
@@ -127,6 +154,12 @@ impl Block {
             in_len:   0,
             out_len:  0,
             raw_data: String::new(),
+            reward:   0.0,
+            cb_out:   String::new(),
+            cb_ker:   String::new(),
+            maturity: 0,
+            sums_ok:  None,
+            sigs_ok:  0,
         }
     }
 }
@@ -135,23 +168,108 @@ impl Block {
 // Kernel data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Kernel {
-    pub height:   String,
-    pub excess:   String,
-    pub ker_type: String,
-    pub fee:      String,
-    pub status:   String,
-    pub raw_data: String,
+    pub height:     String,
+    pub excess:     String,
+    pub ker_type:   String,
+    pub fee:        String,
+    pub status:     String,
+    pub raw_data:   String,
+    pub features:   Option<KernelFeatures>,
+    // Heights of other NRD kernels with the same excess, from the chain index
+    pub duplicates: Vec<u64>,
+    // Signature check, None if not verified
+    pub verified:   Option<bool>,
 }
 
 impl Kernel {
     pub fn new() -> Kernel {
         Kernel {
-            height:   String::new(),
-            excess:   String::new(),
-            ker_type: String::new(),
-            fee:      String::new(),
-            status:   String::new(),
-            raw_data: String::new(),
+            height:     String::new(),
+            excess:     String::new(),
+            ker_type:   String::new(),
+            fee:        String::new(),
+            status:     String::new(),
+            raw_data:   String::new(),
+            features:   None,
+            duplicates: Vec::new(),
+            verified:   None,
+        }
+    }
+}
+
+
+// Fee bits of the kernel fee field, the rest is the fee shift
+pub const FEE_MASK: u64 = 0xff_ffff_ffff;
+
+
+// Kernel features with their fee and lock parameters
+// https://github.com/mimblewimble/grin/blob/master/core/src/core/transaction.rs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum KernelFeatures {
+    Plain             { fee: u64 },
+    Coinbase,
+    HeightLocked      { fee: u64, lock_height: u64 },
+    NoRecentDuplicate { fee: u64, relative_height: u64 },
+}
+
+impl KernelFeatures {
+    // Parsing transaction kernel features, e.g. "Coinbase" or {"Plain": {"fee": 1000000}}.
+    pub fn from_json(features: &Value) -> Option<KernelFeatures> {
+        if features.as_str() == Some("Coinbase") {
+            return Some(KernelFeatures::Coinbase);
+        }
+
+        let (name, fields) = features.as_object()?.iter().next()?;
+        let fee            = fields["fee"].as_u64()? & FEE_MASK;
+
+        match name.as_str() {
+            "Plain"             => Some(KernelFeatures::Plain { fee }),
+            "HeightLocked"      => Some(KernelFeatures::HeightLocked { fee, lock_height: fields["lock_height"].as_u64()? }),
+            "NoRecentDuplicate" => Some(KernelFeatures::NoRecentDuplicate { fee, relative_height: fields["relative_height"].as_u64()? }),
+            _                   => None,
+        }
+    }
+
+    // Parsing block kernel features, the features name with fee and lock_height fields
+    // (lock_height holds relative height of NRD kernels).
+    pub fn from_printable(kernel: &Value) -> Option<KernelFeatures> {
+        let fee    = kernel["fee"].as_u64().unwrap_or_default();
+        let height = kernel["lock_height"].as_u64().unwrap_or_default();
+
+        match kernel["features"].as_str()? {
+            "Plain"             => Some(KernelFeatures::Plain { fee }),
+            "Coinbase"          => Some(KernelFeatures::Coinbase),
+            "HeightLocked"      => Some(KernelFeatures::HeightLocked { fee, lock_height: height }),
+            "NoRecentDuplicate" => Some(KernelFeatures::NoRecentDuplicate { fee, relative_height: height }),
+            _                   => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KernelFeatures::Plain { .. }             => "Plain",
+            KernelFeatures::Coinbase                 => "Coinbase",
+            KernelFeatures::HeightLocked { .. }      => "HeightLocked",
+            KernelFeatures::NoRecentDuplicate { .. } => "NoRecentDuplicate",
+        }
+    }
+
+    pub fn fee(&self) -> u64 {
+        match self {
+            KernelFeatures::Plain { fee }                 => *fee,
+            KernelFeatures::Coinbase                      => 0,
+            KernelFeatures::HeightLocked { fee, .. }      => *fee,
+            KernelFeatures::NoRecentDuplicate { fee, .. } => *fee,
+        }
+    }
+
+    // Lock parameter description, empty for kernels without locks.
+    pub fn lock(&self) -> String {
+        match self {
+            KernelFeatures::HeightLocked { lock_height, .. }          => format!("Lock Height {}", lock_height),
+            KernelFeatures::NoRecentDuplicate { relative_height, .. } => format!("Relative Height {}", relative_height),
+            _                                                         => String::new(),
         }
     }
 }
@@ -164,6 +282,8 @@ pub struct Transactions {
     pub period_24h: String,
     pub fees_1h:    String,
     pub fees_24h:   String,
+    // Immature coinbase outputs: height, commitment and reward (GRIN), newest first
+    pub coinbase:   Vec<(u64, String, f64)>,
 }
 
 impl Transactions {
@@ -173,13 +293,14 @@ impl Transactions {
             period_24h: String::new(),
             fees_1h:    String::new(),
             fees_24h:   String::new(),
+            coinbase:   Vec::new(),
         }
     }
 }
 
 
 // Explorer configuration
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExplorerConfig {
     pub host:                    String,
     pub port:                    String,
@@ -195,6 +316,35 @@ pub struct ExplorerConfig {
     pub stats_source:            Vec<String>,
     pub public_nodes:            Vec<String>,
     pub database:                String,
+    pub batch_limit:             usize,
+    pub api_keys:                Vec<ApiKey>,
+    pub admin_token:             String,
+    pub upstreams:               Vec<Upstream>,
+    pub load_balance:            String,
+    pub max_height_lag:          u64,
+    pub name:                    String,
+    pub url:                     String,
+    pub networks:                Vec<String>,
+    pub http_timeout:            u64,
+    pub connect_timeout:         u64,
+    pub http_retries:            u32,
+    pub retry_backoff:           u64,
+    pub http_proxy:              String,
+    pub ca_cert_path:            String,
+    pub probe_timeout:           u64,
+    pub probe_concurrency:       usize,
+    pub crawl_interval:          u64,
+    pub pubnode_interval:        i64,
+    pub indexer:                 String,
+    pub geoip_db:                String,
+    pub geoip_asn_db:            String,
+    pub geoip_fallback:          String,
+    pub geoip_cache_ttl:         i64,
+    pub price_sources:           Vec<PriceSource>,
+    pub price_aggregation:       String,
+    pub price_interval:          i64,
+    pub price_stale:             i64,
+    pub currencies:              Vec<String>,
 }
 
 impl ExplorerConfig {
@@ -214,6 +364,141 @@ impl ExplorerConfig {
             stats_source:            Vec::new(),
             public_nodes:            Vec::new(),
             database:                String::new(),
+            batch_limit:             100,
+            api_keys:                Vec::new(),
+            admin_token:             String::new(),
+            upstreams:               Vec::new(),
+            load_balance:            String::new(),
+            max_height_lag:          5,
+            name:                    "Mainnet".to_string(),
+            url:                     String::new(),
+            networks:                Vec::new(),
+            http_timeout:            10,
+            connect_timeout:         5,
+            http_retries:            2,
+            retry_backoff:           500,
+            http_proxy:              String::new(),
+            ca_cert_path:            String::new(),
+            probe_timeout:           3000,
+            probe_concurrency:       32,
+            crawl_interval:          3600,
+            pubnode_interval:        60,
+            indexer:                 "disabled".to_string(),
+            geoip_db:                String::new(),
+            geoip_asn_db:            String::new(),
+            geoip_fallback:          String::new(),
+            geoip_cache_ttl:         604800,
+            price_sources:           Vec::new(),
+            price_aggregation:       "median".to_string(),
+            price_interval:          300,
+            price_stale:             900,
+            currencies:              vec!["usd".to_string()],
+        }
+    }
+}
+
+
+// Supply at a given height or date
+#[derive(Debug, Clone, Serialize)]
+pub struct SupplyPoint {
+    pub height:    u64,
+    pub date:      String,
+    // Supply in grin
+    pub supply:    u64,
+    // Annual inflation in percent
+    pub inflation: f64,
+    // Whether the height is not mined yet
+    pub projected: bool,
+}
+
+
+// Peer geolocation
+#[derive(Debug, Clone)]
+pub struct GeoLocation {
+    pub country:      String,
+    pub country_code: String,
+    pub isp:          String,
+    pub asn:          u32,
+}
+
+impl GeoLocation {
+    pub fn new() -> GeoLocation {
+        GeoLocation {
+            country:      String::new(),
+            country_code: String::new(),
+            isp:          String::new(),
+            asn:          0,
+        }
+    }
+}
+
+
+// Price feed source settings
+#[derive(Debug, Clone)]
+pub struct PriceSource {
+    // coingecko, ticker or file
+    pub kind:       String,
+    pub name:       String,
+    pub url:        String,
+    pub path:       String,
+    // JSON pointers to the ticker fields
+    pub usd:        String,
+    pub btc:        String,
+    pub volume_usd: String,
+    pub volume_btc: String,
+    pub priority:   i64,
+}
+
+impl PriceSource {
+    pub fn new() -> PriceSource {
+        PriceSource {
+            kind:       String::new(),
+            name:       String::new(),
+            url:        String::new(),
+            path:       String::new(),
+            usd:        String::new(),
+            btc:        String::new(),
+            volume_usd: String::new(),
+            volume_btc: String::new(),
+            priority:   0,
+        }
+    }
+}
+
+
+// Price sample returned by a price provider
+#[derive(Debug, Clone, Serialize)]
+pub struct Price {
+    pub source:     String,
+    pub usd:        Option<f64>,
+    pub btc:        Option<f64>,
+    pub volume_usd: Option<f64>,
+    pub volume_btc: Option<f64>,
+    // Prices in other fiat currencies, by currency code
+    pub fiat:       HashMap<String, f64>,
+    // Unix timestamp of the sample
+    pub time:       i64,
+}
+
+impl Price {
+    pub fn new(source: &str) -> Price {
+        Price {
+            source:     source.to_string(),
+            usd:        None,
+            btc:        None,
+            volume_usd: None,
+            volume_btc: None,
+            fiat:       HashMap::new(),
+            time:       0,
+        }
+    }
+
+    // Price in a fiat currency, USD is kept in its own field.
+    pub fn fiat_or_usd(&self, code: &str) -> Option<f64> {
+        if code == "usd" {
+            self.usd
+        } else {
+            self.fiat.get(code).copied()
         }
     }
 }
@@ -222,21 +507,40 @@ impl ExplorerConfig {
 // Output data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Output {
-    pub height:   String,
-    pub commit:   String,
-    pub out_type: String,
-    pub status:   String,
-    pub raw_data: String,
+    pub height:       String,
+    pub hash:         String,
+    pub commit:       String,
+    pub out_type:     String,
+    pub status:       String,
+    pub raw_data:     String,
+    // Spending block, from the chain index
+    pub spent_height: String,
+    pub spent_hash:   String,
+    // Range proof (hex) and its hash
+    pub proof:        String,
+    pub proof_hash:   String,
+    pub mmr_index:    String,
+    pub merkle_proof: String,
+    // Blocks until a coinbase output can be spent, 0 when mature
+    pub maturity:     u64,
 }
 
 impl Output {
     pub fn new() -> Output {
         Output {
-            height:   String::new(),
-            commit:   String::new(),
-            out_type: String::new(),
-            status:   String::new(),
-            raw_data: String::new(),
+            height:       String::new(),
+            hash:         String::new(),
+            commit:       String::new(),
+            out_type:     String::new(),
+            status:       String::new(),
+            raw_data:     String::new(),
+            spent_height: String::new(),
+            spent_hash:   String::new(),
+            proof:        String::new(),
+            proof_hash:   String::new(),
+            mmr_index:    String::new(),
+            merkle_proof: String::new(),
+            maturity:     0,
         }
     }
 }
@@ -259,6 +563,14 @@ pub struct Statistics {
     pub utxos: Vec<String>,
     // Kernels
     pub kernels:    Vec<String>,
+    // Daily average price & market cap (USD)
+    pub price:      Vec<String>,
+    pub mcap:       Vec<String>,
+    // Daily network size (known & reachable peers)
+    pub peers_known: Vec<String>,
+    pub peers_reach: Vec<String>,
+    // Daily node versions & capabilities snapshots
+    pub versions:    Vec<VersionCount>,
 }
 
 impl Statistics {
@@ -273,31 +585,141 @@ impl Statistics {
             fees:       Vec::new(),
             utxos: Vec::new(),
             kernels:    Vec::new(),
+            price:      Vec::new(),
+            mcap:       Vec::new(),
+            peers_known: Vec::new(),
+            peers_reach: Vec::new(),
+            versions:    Vec::new(),
         }
     }
 }
 
+// Number of nodes by user agent, protocol version or capability on a date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionCount {
+    pub date:  String,
+    // "user_agent", "protocol", "capability" or "total"
+    pub kind:  String,
+    pub name:  String,
+    pub count: u32,
+}
+
+
+// Block data stored in the chain index
+#[derive(Debug, Clone)]
+pub struct IndexedBlock {
+    pub height:    u64,
+    pub hash:      String,
+    pub timestamp: i64,
+    // Excess, features, fee and lock height
+    pub kernels:   Vec<(String, String, u64, u64)>,
+    // Commitment, output type and MMR index
+    pub outputs:   Vec<(String, String, u64)>,
+    // Spent commitments
+    pub inputs:    Vec<String>,
+}
+
+
+// Block height, hash and time (unix seconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockTime {
+    pub height:    u64,
+    pub hash:      String,
+    pub timestamp: i64,
+}
+
+
+// Mempool transaction recorded by the explorer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    pub id:          i64,
+    // Excess, features and fee
+    pub kernels:     Vec<(String, String, u64)>,
+    pub inputs:      Vec<String>,
+    pub outputs:     Vec<String>,
+    pub fee:         u64,
+    pub weight:      f64,
+    // Time and chain height when the transaction was seen in the mempool
+    pub seen:        i64,
+    pub seen_height: u64,
+    // Confirmation block height and time, 0 while unconfirmed
+    pub height:      u64,
+    pub confirmed:   i64,
+}
+
+
+// Payment proof check: signatures of the sender and the recipient over the amount, kernel excess
+// and sender address, and the kernel height with its number of confirmations (None if not on chain)
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentProof {
+    pub amount:        u64,
+    pub excess:        String,
+    pub sender:        String,
+    pub recipient:     String,
+    pub sender_sig:    bool,
+    pub recipient_sig: bool,
+    pub height:        Option<u64>,
+    pub confirmations: u64,
+    pub verified:      bool,
+}
+
+
+// Search match: "block", "kernel" or "output", its hash or commitment and block height
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub kind:   String,
+    pub id:     String,
+    pub height: u64,
+}
+
+
 // Public node data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicNode {
-    pub name:    String,
-    pub version: String,
-    pub height:  String,
-    pub hash:    String,
+    pub name:        String,
+    pub version:     String,
+    pub height:      String,
+    pub hash:        String,
+    // Monitor data: latest probe, "behind", "forked" or "offline" status (empty if the node is fine),
+    // uptime and average latency over the kept history, daily uptime (oldest first)
+    pub online:      bool,
+    pub latency:     u64,
+    pub status:      String,
+    pub uptime:      String,
+    pub avg_latency: String,
+    pub history:     Vec<String>,
 }
 
 impl PublicNode {
     pub fn new() -> PublicNode {
         PublicNode {
-            name:    String::new(),
-            version: String::new(),
-            height:  String::new(),
-            hash:    String::new(),
+            name:        String::new(),
+            version:     String::new(),
+            height:      String::new(),
+            hash:        String::new(),
+            online:      false,
+            latency:     0,
+            status:      String::new(),
+            uptime:      String::new(),
+            avg_latency: String::new(),
+            history:     Vec::new(),
         }
     }
 }
 
 
+// Public node probes of a day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubNodeDay {
+    pub name:    String,
+    pub date:    String,
+    pub probes:  u32,
+    pub online:  u32,
+    // Sum of successful probes latency, ms
+    pub latency: u64,
+}
+
+
 // Connected node data
 #[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectedNode {
@@ -308,6 +730,19 @@ pub struct ConnectedNode {
     pub location:    String,
     pub flag:        String,
     pub isp:         String,
+    pub asn:         u32,
+    // Crawler data, timestamps of the first and the latest successful probe
+    pub first_seen:  i64,
+    pub last_seen:   i64,
+    pub probes:      u32,
+    pub successes:   u32,
+    pub reachable:   bool,
+    // Handshake data: protocol version, total difficulty, genesis hash and
+    // status ("stale", "wrong-genesis", "non-grin" or the other network name, empty if the node is fine)
+    pub version:     u32,
+    pub difficulty:  u64,
+    pub genesis:     String,
+    pub status:      String,
 }
 
 impl ConnectedNode {
@@ -320,6 +755,16 @@ impl ConnectedNode {
             location:    String::new(),
             flag:        String::new(),
             isp:         String::new(),
+            asn:         0,
+            first_seen:  0,
+            last_seen:   0,
+            probes:      0,
+            successes:   0,
+            reachable:   false,
+            version:     0,
+            difficulty:  0,
+            genesis:     String::new(),
+            status:      String::new(),
         }
     }
 }
@@ -331,6 +776,11 @@ pub struct NetStats {
     pub pub_nodes:   Vec<PublicNode>,
     pub reach_nodes: Vec<ConnectedNode>,
     pub conn_nodes:  Vec<ConnectedNode>,
+    // All peers known to the crawler
+    pub peers:       Vec<ConnectedNode>,
+    pub crawled:     i64,
+    // Public nodes monitor history
+    pub pub_days:    Vec<PubNodeDay>,
 }
 
 impl NetStats {
@@ -339,7 +789,119 @@ impl NetStats {
             pub_nodes:   Vec::new(),
             reach_nodes: Vec::new(),
             conn_nodes:  Vec::new(),
+            peers:       Vec::new(),
+            crawled:     0,
+            pub_days:    Vec::new(),
+        }
+    }
+}
+
+
+
+// API key data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub key:        String,
+    pub name:       String,
+    // Methods allowed in addition to the public ones
+    pub methods:    Vec<String>,
+    // Requests per minute, 0 means no limit
+    pub rate_limit: u32,
+    // Usage
+    pub requests:   u64,
+    pub last_used:  String,
+    // Current rate limit window (minutes since epoch) and its requests count
+    pub window:     i64,
+    pub window_cnt: u32,
+}
+
+impl ApiKey {
+    pub fn new() -> ApiKey {
+        ApiKey {
+            key:        String::new(),
+            name:       String::new(),
+            methods:    Vec::new(),
+            rate_limit: 0,
+            requests:   0,
+            last_used:  String::new(),
+            window:     0,
+            window_cnt: 0,
+        }
+    }
+}
+
+
+// Upstream node data
+#[derive(Debug, Clone, Serialize)]
+pub struct Upstream {
+    pub name:               String,
+    pub url:                String,
+    #[serde(skip)]
+    pub user:               String,
+    #[serde(skip)]
+    pub api_secret:         String,
+    #[serde(skip)]
+    pub foreign_api_secret: String,
+    // Health
+    pub healthy:            bool,
+    pub status:             String,
+    pub height:             u64,
+    pub latency:            u64,
+}
+
+impl Upstream {
+    pub fn new() -> Upstream {
+        Upstream {
+            name:               String::new(),
+            url:                String::new(),
+            user:               String::new(),
+            api_secret:         String::new(),
+            foreign_api_secret: String::new(),
+            healthy:            true,
+            status:             String::new(),
+            height:             0,
+            latency:            0,
         }
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn kernel_features_json() {
+        assert_eq!(KernelFeatures::from_json(&json!("Coinbase")), Some(KernelFeatures::Coinbase));
+        assert_eq!(KernelFeatures::from_json(&json!({"Plain": {"fee": 23500000}})),
+                   Some(KernelFeatures::Plain { fee: 23500000 }));
+        assert_eq!(KernelFeatures::from_json(&json!({"HeightLocked": {"fee": 7000000, "lock_height": 2000000}})),
+                   Some(KernelFeatures::HeightLocked { fee: 7000000, lock_height: 2000000 }));
+        assert_eq!(KernelFeatures::from_json(&json!({"NoRecentDuplicate": {"fee": 7000000, "relative_height": 1440}})),
+                   Some(KernelFeatures::NoRecentDuplicate { fee: 7000000, relative_height: 1440 }));
+
+        // Fee shift bits are not part of the fee
+        assert_eq!(KernelFeatures::from_json(&json!({"Plain": {"fee": (2u64 << 40) | 23500000}})),
+                   Some(KernelFeatures::Plain { fee: 23500000 }));
+
+        assert_eq!(KernelFeatures::from_json(&json!({"Plain": {}})), None);
+        assert_eq!(KernelFeatures::from_json(&json!({"HeightLocked": {"fee": 7000000}})), None);
+        assert_eq!(KernelFeatures::from_json(&json!({"Unknown": {"fee": 7000000}})), None);
+        assert_eq!(KernelFeatures::from_json(&json!("Plain")), None);
+    }
+
+    #[test]
+    fn kernel_features_printable() {
+        let kernel = json!({"features": "NoRecentDuplicate", "fee": 7000000, "lock_height": 1440});
+        let nrd    = KernelFeatures::from_printable(&kernel).unwrap();
+
+        assert_eq!(nrd, KernelFeatures::NoRecentDuplicate { fee: 7000000, relative_height: 1440 });
+        assert_eq!((nrd.name(), nrd.fee(), nrd.lock()), ("NoRecentDuplicate", 7000000, "Relative Height 1440".to_string()));
+
+        let coinbase = KernelFeatures::from_printable(&json!({"features": "Coinbase", "fee": 0, "lock_height": 0})).unwrap();
+
+        assert_eq!((coinbase.name(), coinbase.fee(), coinbase.lock()), ("Coinbase", 0, String::new()));
+        assert_eq!(KernelFeatures::from_printable(&json!({"features": {"Plain": {"fee": 1}}})), None);
+    }
+}
@@ -1,4 +1,10 @@
-use rusqlite::{Connection, Result};
+use chrono::DateTime;
+use rusqlite::{Connection, Result, Row};
+use std::collections::HashMap;
+
+use crate::data::{ApiKey, BlockTime, ConnectedNode, IndexedBlock, Output, Price, PubNodeDay, PublicNode, SearchResult,
+                  Transaction, VersionCount};
+
 
 pub fn open_db_connection(db_name: &str) -> Result<Connection> {
     let conn = Connection::open(db_name)?;
@@ -38,3 +44,822 @@ pub fn read_row(conn: &Connection, row_name: &str) -> Result<Vec<String>> {
     Ok(data)
 }
 
+
+
+// Adding a column to a table created by an older version, if it is missing.
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns  = stmt.query_map([], |row| row.get::<_, String>(1))?.collect::<Result<Vec<String>>>()?;
+
+    if !columns.iter().any(|c| c == column) {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), ())?;
+    }
+
+    Ok(())
+}
+
+// Keys come from the config (source "config") or are added to the table manually (source "manual").
+pub fn create_api_keys_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS api_keys (
+            id         INTEGER PRIMARY KEY,
+            key        TEXT NOT NULL UNIQUE,
+            name       TEXT NOT NULL,
+            methods    TEXT NOT NULL,
+            rate_limit INTEGER NOT NULL,
+            requests   INTEGER NOT NULL DEFAULT 0,
+            last_used  TEXT NOT NULL DEFAULT '',
+            source     TEXT NOT NULL DEFAULT 'manual'
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    add_column(conn, "api_keys", "source", "TEXT NOT NULL DEFAULT 'manual'")?;
+
+    Ok(())
+}
+
+// Keys defined in the config take precedence over the stored ones, usage counters are kept.
+pub fn save_api_key(conn: &Connection, key: &ApiKey) -> Result<()> {
+    conn.execute(
+        "INSERT INTO api_keys (key, name, methods, rate_limit, source) VALUES (?1, ?2, ?3, ?4, 'config')
+         ON CONFLICT(key) DO UPDATE SET name = ?2, methods = ?3, rate_limit = ?4, source = 'config'",
+        (&key.key, &key.name, &key.methods.join(","), &key.rate_limit),
+    )?;
+
+    Ok(())
+}
+
+// Removing keys that were taken from the config, but are not listed there anymore.
+pub fn remove_config_api_keys(conn: &Connection, keep: &[ApiKey]) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT key FROM api_keys WHERE source = 'config'")?;
+    let stored   = stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<Result<Vec<String>>>()?;
+
+    for key in stored.iter().filter(|k| !keep.iter().any(|c| c.key == **k)) {
+        conn.execute("DELETE FROM api_keys WHERE key = ?1", [key])?;
+    }
+
+    Ok(())
+}
+
+pub fn read_api_keys(conn: &Connection) -> Result<Vec<ApiKey>> {
+    let mut stmt = conn.prepare("SELECT key, name, methods, rate_limit, requests, last_used FROM api_keys ORDER BY id")?;
+
+    let data_iter = stmt
+        .query_map([], |row| {
+            let mut key         = ApiKey::new();
+            let methods: String = row.get(2)?;
+
+            key.key        = row.get(0)?;
+            key.name       = row.get(1)?;
+            key.methods    = methods.split(',').filter(|m| !m.is_empty()).map(|m| m.to_string()).collect();
+            key.rate_limit = row.get(3)?;
+            key.requests   = row.get(4)?;
+            key.last_used  = row.get(5)?;
+
+            Ok(key)
+        })?;
+
+    data_iter.collect()
+}
+
+pub fn update_api_key_usage(conn: &Connection, key: &ApiKey) -> Result<()> {
+    conn.execute(
+        "UPDATE api_keys SET requests = ?1, last_used = ?2 WHERE key = ?3",
+        (&key.requests, &key.last_used, &key.key),
+    )?;
+
+    Ok(())
+}
+
+
+
+pub fn create_prices_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS prices (
+            id         INTEGER PRIMARY KEY,
+            time       INTEGER NOT NULL,
+            date       TEXT NOT NULL,
+            source     TEXT NOT NULL,
+            usd        REAL,
+            btc        REAL,
+            volume_usd REAL,
+            volume_btc REAL,
+            cap_usd    REAL,
+            fiat       TEXT NOT NULL DEFAULT '{}'
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS prices_date ON prices (date)", ())?;
+
+    Ok(())
+}
+
+pub fn save_price(conn: &Connection, price: &Price, cap_usd: Option<f64>) -> Result<()> {
+    let date = DateTime::from_timestamp(price.time, 0).unwrap_or_default().format("%Y-%m-%d").to_string();
+
+    conn.execute(
+        "INSERT INTO prices (time, date, source, usd, btc, volume_usd, volume_btc, cap_usd, fiat)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        (&price.time, &date, &price.source, &price.usd, &price.btc, &price.volume_usd, &price.volume_btc,
+         &cap_usd, &serde_json::to_string(&price.fiat).unwrap()),
+    )?;
+
+    Ok(())
+}
+
+// Date (YYYY-MM-DD), average price and market cap in USD.
+pub type DailyPrice = (String, Option<f64>, Option<f64>);
+
+pub fn read_daily_prices(conn: &Connection) -> Result<Vec<DailyPrice>> {
+    let mut stmt = conn.prepare("SELECT date, AVG(usd), AVG(cap_usd) FROM prices GROUP BY date ORDER BY date")?;
+
+    let data_iter = stmt
+        .query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+
+    data_iter.collect()
+}
+
+// Average price of a day (YYYY-MM-DD), None if there are no samples.
+pub fn read_price_on(conn: &Connection, date: &str) -> Result<Option<Price>> {
+    let mut stmt = conn.prepare("SELECT usd, btc, fiat FROM prices WHERE date = ?1")?;
+
+    let rows = stmt
+        .query_map([date], |row| {
+            let fiat: String = row.get(2)?;
+
+            Ok((row.get::<_, Option<f64>>(0)?, row.get::<_, Option<f64>>(1)?,
+                serde_json::from_str::<HashMap<String, f64>>(&fiat).unwrap_or_default()))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let avg = |values: Vec<f64>| if values.is_empty() { None } else { Some(values.iter().sum::<f64>() / values.len() as f64) };
+
+    let mut price = Price::new(date);
+
+    price.usd = avg(rows.iter().filter_map(|r| r.0).collect());
+    price.btc = avg(rows.iter().filter_map(|r| r.1).collect());
+
+    let codes: Vec<String> = rows.iter().flat_map(|r| r.2.keys().cloned()).collect();
+
+    for code in codes {
+        if let Some(v) = avg(rows.iter().filter_map(|r| r.2.get(&code).copied()).collect()) {
+            price.fiat.insert(code, v);
+        }
+    }
+
+    Ok(Some(price))
+}
+
+
+
+pub fn create_peers_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS peers (
+            id         INTEGER PRIMARY KEY,
+            address    TEXT NOT NULL UNIQUE,
+            user_agent TEXT NOT NULL,
+            bits       INTEGER NOT NULL,
+            first_seen INTEGER NOT NULL,
+            last_seen  INTEGER NOT NULL,
+            probes     INTEGER NOT NULL,
+            successes  INTEGER NOT NULL,
+            reachable  INTEGER NOT NULL
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    // Handshake data of peers
+    add_column(conn, "peers", "version", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column(conn, "peers", "difficulty", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column(conn, "peers", "genesis", "TEXT NOT NULL DEFAULT ''")?;
+    add_column(conn, "peers", "status", "TEXT NOT NULL DEFAULT ''")?;
+
+    // GeoIP data of peers
+    add_column(conn, "peers", "location", "TEXT NOT NULL DEFAULT ''")?;
+    add_column(conn, "peers", "flag", "TEXT NOT NULL DEFAULT ''")?;
+    add_column(conn, "peers", "isp", "TEXT NOT NULL DEFAULT ''")?;
+    add_column(conn, "peers", "asn", "INTEGER NOT NULL DEFAULT 0")?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS network_size (
+            id        INTEGER PRIMARY KEY,
+            date      TEXT NOT NULL UNIQUE,
+            known     INTEGER NOT NULL,
+            reachable INTEGER NOT NULL
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    Ok(())
+}
+
+pub fn save_peers(conn: &Connection, peers: &[ConnectedNode]) -> Result<()> {
+    conn.execute("BEGIN", ())?;
+
+    // Forgotten peers are removed
+    conn.execute("DELETE FROM peers", ())?;
+
+    for peer in peers.iter() {
+        conn.execute(
+            "INSERT INTO peers (address, user_agent, bits, first_seen, last_seen, probes, successes, reachable,
+                                version, difficulty, genesis, status, location, flag, isp, asn)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            (&peer.address, &peer.user_agent, &peer.bits, &peer.first_seen, &peer.last_seen, &peer.probes,
+             &peer.successes, &peer.reachable, &peer.version, &peer.difficulty, &peer.genesis, &peer.status,
+             &peer.location, &peer.flag, &peer.isp, &peer.asn),
+        )?;
+    }
+
+    conn.execute("COMMIT", ())?;
+
+    Ok(())
+}
+
+pub fn read_peers(conn: &Connection) -> Result<Vec<ConnectedNode>> {
+    let mut stmt = conn.prepare("SELECT address, user_agent, bits, first_seen, last_seen, probes, successes, reachable,
+                                        version, difficulty, genesis, status, location, flag, isp, asn FROM peers")?;
+
+    let data_iter = stmt
+        .query_map([], |row| {
+            let mut peer = ConnectedNode::new();
+
+            peer.address     = row.get(0)?;
+            peer.user_agent  = row.get(1)?;
+            peer.bits        = row.get(2)?;
+            peer.is_archival = peer.bits & (1 << 5) != 0;
+            peer.first_seen  = row.get(3)?;
+            peer.last_seen   = row.get(4)?;
+            peer.probes      = row.get(5)?;
+            peer.successes   = row.get(6)?;
+            peer.reachable   = row.get(7)?;
+            peer.version     = row.get(8)?;
+            peer.difficulty  = row.get(9)?;
+            peer.genesis     = row.get(10)?;
+            peer.status      = row.get(11)?;
+            peer.location    = row.get(12)?;
+            peer.flag        = row.get(13)?;
+            peer.isp         = row.get(14)?;
+            peer.asn         = row.get(15)?;
+
+            Ok(peer)
+        })?;
+
+    data_iter.collect()
+}
+
+// The latest crawl of the day is kept.
+pub fn save_network_size(conn: &Connection, date: &str, known: usize, reachable: usize) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO network_size (date, known, reachable) VALUES (?1, ?2, ?3)",
+        (date, known, reachable),
+    )?;
+
+    Ok(())
+}
+
+// Date (YYYY-MM-DD), known and reachable peers.
+pub fn read_network_size(conn: &Connection) -> Result<Vec<(String, u32, u32)>> {
+    let mut stmt = conn.prepare("SELECT date, known, reachable FROM network_size ORDER BY date")?;
+
+    let data_iter = stmt
+        .query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+
+    data_iter.collect()
+}
+
+
+
+pub fn create_versions_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS versions (
+            id    INTEGER PRIMARY KEY,
+            date  TEXT NOT NULL,
+            kind  TEXT NOT NULL,
+            name  TEXT NOT NULL,
+            count INTEGER NOT NULL,
+            UNIQUE (date, kind, name)
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    Ok(())
+}
+
+// Snapshot of a day replaces the previous one.
+pub fn save_versions(conn: &Connection, date: &str, counts: &[VersionCount]) -> Result<()> {
+    conn.execute("BEGIN", ())?;
+    conn.execute("DELETE FROM versions WHERE date = ?1", [date])?;
+
+    for c in counts.iter() {
+        conn.execute(
+            "INSERT INTO versions (date, kind, name, count) VALUES (?1, ?2, ?3, ?4)",
+            (&c.date, &c.kind, &c.name, &c.count),
+        )?;
+    }
+
+    conn.execute("COMMIT", ())?;
+
+    Ok(())
+}
+
+pub fn read_versions(conn: &Connection) -> Result<Vec<VersionCount>> {
+    let mut stmt = conn.prepare("SELECT date, kind, name, count FROM versions ORDER BY date, kind, count DESC")?;
+
+    let data_iter = stmt
+        .query_map([], |row| {
+            Ok(VersionCount {
+                date:  row.get(0)?,
+                kind:  row.get(1)?,
+                name:  row.get(2)?,
+                count: row.get(3)?,
+            })
+        })?;
+
+    data_iter.collect()
+}
+
+
+
+pub fn create_pubnode_probes_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pubnode_probes (
+            id      INTEGER PRIMARY KEY,
+            time    INTEGER NOT NULL,
+            date    TEXT NOT NULL,
+            name    TEXT NOT NULL,
+            online  INTEGER NOT NULL,
+            latency INTEGER NOT NULL,
+            height  TEXT NOT NULL,
+            hash    TEXT NOT NULL,
+            status  TEXT NOT NULL
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS pubnode_probes_date ON pubnode_probes (date)", ())?;
+
+    Ok(())
+}
+
+pub fn save_pubnode_probe(conn: &Connection, time: i64, node: &PublicNode) -> Result<()> {
+    let date = DateTime::from_timestamp(time, 0).unwrap_or_default().format("%Y-%m-%d").to_string();
+
+    conn.execute(
+        "INSERT INTO pubnode_probes (time, date, name, online, latency, height, hash, status)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        (&time, &date, &node.name, &node.online, &node.latency, &node.height, &node.hash, &node.status),
+    )?;
+
+    Ok(())
+}
+
+// Removing probes before the date (YYYY-MM-DD).
+pub fn prune_pubnode_probes(conn: &Connection, date: &str) -> Result<()> {
+    conn.execute("DELETE FROM pubnode_probes WHERE date < ?1", [date])?;
+
+    Ok(())
+}
+
+// Probes aggregated by node and day, since the date (YYYY-MM-DD).
+pub fn read_pubnode_days(conn: &Connection, date: &str) -> Result<Vec<PubNodeDay>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, date, COUNT(*), SUM(online), SUM(CASE WHEN online THEN latency ELSE 0 END)
+         FROM pubnode_probes WHERE date >= ?1 GROUP BY name, date ORDER BY date")?;
+
+    let data_iter = stmt
+        .query_map([date], |row| {
+            Ok(PubNodeDay {
+                name:    row.get(0)?,
+                date:    row.get(1)?,
+                probes:  row.get(2)?,
+                online:  row.get(3)?,
+                latency: row.get(4)?,
+            })
+        })?;
+
+    data_iter.collect()
+}
+
+
+
+// Chain index: blocks, kernels, outputs and inputs by height, used for search and spent outputs.
+pub fn create_index_tables(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS blocks (
+            height    INTEGER PRIMARY KEY,
+            hash      TEXT NOT NULL,
+            timestamp INTEGER NOT NULL
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS kernels (
+            id          INTEGER PRIMARY KEY,
+            excess      TEXT NOT NULL,
+            height      INTEGER NOT NULL,
+            features    TEXT NOT NULL,
+            fee         INTEGER NOT NULL,
+            lock_height INTEGER NOT NULL
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS outputs (
+            id          INTEGER PRIMARY KEY,
+            commitment  TEXT NOT NULL,
+            height      INTEGER NOT NULL,
+            output_type TEXT NOT NULL,
+            mmr_index   INTEGER NOT NULL
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inputs (
+            id          INTEGER PRIMARY KEY,
+            commitment  TEXT NOT NULL,
+            height      INTEGER NOT NULL
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS blocks_hash ON blocks (hash)", ())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS blocks_timestamp ON blocks (timestamp)", ())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS kernels_excess ON kernels (excess)", ())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS kernels_height ON kernels (height)", ())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS outputs_commit ON outputs (commitment)", ())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS outputs_height ON outputs (height)", ())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS inputs_commit ON inputs (commitment)", ())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS inputs_height ON inputs (height)", ())?;
+
+    Ok(())
+}
+
+pub fn read_indexed_tip(conn: &Connection) -> Result<Option<(u64, String)>> {
+    let mut stmt = conn.prepare("SELECT height, hash FROM blocks ORDER BY height DESC LIMIT 1")?;
+    let mut rows = stmt.query([])?;
+
+    match rows.next()? {
+        Some(row) => Ok(Some((row.get(0)?, row.get(1)?))),
+        None      => Ok(None),
+    }
+}
+
+pub fn save_indexed_block(conn: &Connection, block: &IndexedBlock) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO blocks (height, hash, timestamp) VALUES (?1, ?2, ?3)",
+        (&block.height, &block.hash, &block.timestamp),
+    )?;
+
+    for (excess, features, fee, lock_height) in block.kernels.iter() {
+        conn.execute(
+            "INSERT INTO kernels (excess, height, features, fee, lock_height) VALUES (?1, ?2, ?3, ?4, ?5)",
+            (excess, &block.height, features, fee, lock_height),
+        )?;
+    }
+
+    for (commit, output_type, mmr_index) in block.outputs.iter() {
+        conn.execute(
+            "INSERT INTO outputs (commitment, height, output_type, mmr_index) VALUES (?1, ?2, ?3, ?4)",
+            (commit, &block.height, output_type, mmr_index),
+        )?;
+    }
+
+    for commit in block.inputs.iter() {
+        conn.execute(
+            "INSERT INTO inputs (commitment, height) VALUES (?1, ?2)",
+            (commit, &block.height),
+        )?;
+    }
+
+    Ok(())
+}
+
+// Removing indexed data from the height, used on reorgs.
+pub fn delete_index_from(conn: &Connection, height: u64) -> Result<()> {
+    conn.execute("DELETE FROM blocks WHERE height >= ?1", [height])?;
+    conn.execute("DELETE FROM kernels WHERE height >= ?1", [height])?;
+    conn.execute("DELETE FROM outputs WHERE height >= ?1", [height])?;
+    conn.execute("DELETE FROM inputs WHERE height >= ?1", [height])?;
+
+    Ok(())
+}
+
+// Hex prefix search, prefix is validated by the caller.
+pub fn search_index(conn: &Connection, prefix: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    let mut results = Vec::new();
+
+    let queries = [
+        ("block",  "SELECT hash, height FROM blocks WHERE hash GLOB ?1 LIMIT ?2"),
+        ("kernel", "SELECT excess, height FROM kernels WHERE excess GLOB ?1 LIMIT ?2"),
+        ("output", "SELECT commitment, height FROM outputs WHERE commitment GLOB ?1 LIMIT ?2"),
+    ];
+
+    for (kind, sql) in queries.iter() {
+        let mut stmt = conn.prepare(sql)?;
+
+        let rows = stmt
+            .query_map((format!("{}*", prefix), limit), |row| {
+                Ok(SearchResult { kind: kind.to_string(), id: row.get(0)?, height: row.get(1)? })
+            })?;
+
+        for row in rows {
+            results.push(row?);
+        }
+    }
+
+    Ok(results)
+}
+
+// Height of the first indexed block at or after the timestamp.
+pub fn read_block_at(conn: &Connection, timestamp: i64) -> Result<Option<u64>> {
+    let mut stmt = conn.prepare("SELECT height FROM blocks WHERE timestamp >= ?1 ORDER BY height LIMIT 1")?;
+    let mut rows = stmt.query([timestamp])?;
+
+    match rows.next()? {
+        Some(row) => Ok(Some(row.get(0)?)),
+        None      => Ok(None),
+    }
+}
+
+
+// Indexed block at the height.
+pub fn read_block_time(conn: &Connection, height: u64) -> Result<Option<BlockTime>> {
+    let mut stmt = conn.prepare("SELECT height, hash, timestamp FROM blocks WHERE height = ?1")?;
+    let mut rows = stmt.query([height])?;
+
+    match rows.next()? {
+        Some(row) => Ok(Some(BlockTime { height: row.get(0)?, hash: row.get(1)?, timestamp: row.get(2)? })),
+        None      => Ok(None),
+    }
+}
+
+// Indexed blocks between the heights (inclusive).
+pub fn read_block_times(conn: &Connection, from: u64, to: u64) -> Result<Vec<BlockTime>> {
+    let mut stmt = conn.prepare("SELECT height, hash, timestamp FROM blocks WHERE height BETWEEN ?1 AND ?2 ORDER BY height")?;
+
+    let blocks = stmt
+        .query_map([from, to], |row| {
+            Ok(BlockTime { height: row.get(0)?, hash: row.get(1)?, timestamp: row.get(2)? })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(blocks)
+}
+
+// Heights of the indexed kernels with the excess, NRD kernels may share it.
+pub fn read_kernel_heights(conn: &Connection, excess: &str) -> Result<Vec<u64>> {
+    let mut stmt = conn.prepare("SELECT height FROM kernels WHERE excess = ?1 ORDER BY height")?;
+
+    let heights = stmt
+        .query_map([excess], |row| row.get(0))?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(heights)
+}
+
+// Latest indexed output with the commitment, with the block it was spent in (if any).
+pub fn read_indexed_output(conn: &Connection, commit: &str) -> Result<Option<Output>> {
+    let mut stmt = conn.prepare(
+        "SELECT o.height, b.hash, o.output_type, i.height, s.hash FROM outputs o
+         JOIN blocks b ON b.height = o.height
+         LEFT JOIN inputs i ON i.commitment = o.commitment AND i.height >= o.height
+         LEFT JOIN blocks s ON s.height = i.height
+         WHERE o.commitment = ?1 ORDER BY o.height DESC, i.height LIMIT 1")?;
+    let mut rows = stmt.query([commit])?;
+
+    match rows.next()? {
+        Some(row) => {
+                         let mut output = Output::new();
+
+                         output.commit       = commit.to_string();
+                         output.height       = row.get::<_, u64>(0)?.to_string();
+                         output.hash         = row.get(1)?;
+                         output.out_type     = row.get(2)?;
+                         output.spent_height = row.get::<_, Option<u64>>(3)?.map(|h| h.to_string()).unwrap_or_default();
+                         output.spent_hash   = row.get::<_, Option<String>>(4)?.unwrap_or_default();
+
+                         Ok(Some(output))
+                     },
+        None      => Ok(None),
+    }
+}
+
+
+// Mempool transactions, with their kernels for the lookup by excess.
+pub fn create_transactions_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transactions (
+            id          INTEGER PRIMARY KEY,
+            kernels     TEXT NOT NULL,
+            inputs      TEXT NOT NULL,
+            outputs     TEXT NOT NULL,
+            fee         INTEGER NOT NULL,
+            weight      REAL NOT NULL,
+            seen        INTEGER NOT NULL,
+            seen_height INTEGER NOT NULL,
+            height      INTEGER NOT NULL,
+            confirmed   INTEGER NOT NULL
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tx_kernels (
+            excess TEXT PRIMARY KEY,
+            tx_id  INTEGER NOT NULL
+        )",
+        (), // empty list of parameters.
+    )?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS transactions_height ON transactions (height)", ())?;
+
+    Ok(())
+}
+
+pub fn save_transaction(conn: &Connection, tx: &Transaction) -> Result<()> {
+    conn.execute(
+        "INSERT INTO transactions (kernels, inputs, outputs, fee, weight, seen, seen_height, height, confirmed)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        (serde_json::to_string(&tx.kernels).unwrap_or_default(), serde_json::to_string(&tx.inputs).unwrap_or_default(),
+         serde_json::to_string(&tx.outputs).unwrap_or_default(), &tx.fee, &tx.weight, &tx.seen, &tx.seen_height,
+         &tx.height, &tx.confirmed),
+    )?;
+
+    let id = conn.last_insert_rowid();
+
+    for (excess, _, _) in tx.kernels.iter() {
+        conn.execute("INSERT OR IGNORE INTO tx_kernels (excess, tx_id) VALUES (?1, ?2)", (excess, &id))?;
+    }
+
+    Ok(())
+}
+
+fn read_transaction_row(row: &Row) -> Result<Transaction> {
+    Ok(Transaction {
+        id:          row.get(0)?,
+        kernels:     serde_json::from_str(&row.get::<_, String>(1)?).unwrap_or_default(),
+        inputs:      serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or_default(),
+        outputs:     serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+        fee:         row.get(4)?,
+        weight:      row.get(5)?,
+        seen:        row.get(6)?,
+        seen_height: row.get(7)?,
+        height:      row.get(8)?,
+        confirmed:   row.get(9)?,
+    })
+}
+
+// Transaction containing the kernel.
+pub fn read_transaction(conn: &Connection, excess: &str) -> Result<Option<Transaction>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.kernels, t.inputs, t.outputs, t.fee, t.weight, t.seen, t.seen_height, t.height, t.confirmed
+         FROM transactions t JOIN tx_kernels k ON k.tx_id = t.id WHERE k.excess = ?1")?;
+    let mut rows = stmt.query([excess])?;
+
+    match rows.next()? {
+        Some(row) => Ok(Some(read_transaction_row(row)?)),
+        None      => Ok(None),
+    }
+}
+
+// Transactions not confirmed yet.
+pub fn read_pending_transactions(conn: &Connection) -> Result<Vec<Transaction>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kernels, inputs, outputs, fee, weight, seen, seen_height, height, confirmed
+         FROM transactions WHERE height = 0")?;
+
+    let txs = stmt
+        .query_map([], read_transaction_row)?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(txs)
+}
+
+pub fn confirm_transaction(conn: &Connection, id: i64, height: u64, confirmed: i64) -> Result<()> {
+    conn.execute("UPDATE transactions SET height = ?1, confirmed = ?2 WHERE id = ?3", (&height, &confirmed, &id))?;
+
+    Ok(())
+}
+
+pub fn delete_transaction(conn: &Connection, id: i64) -> Result<()> {
+    conn.execute("DELETE FROM tx_kernels WHERE tx_id = ?1", [id])?;
+    conn.execute("DELETE FROM transactions WHERE id = ?1", [id])?;
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_keys_sources() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        // Table of an older version, without the source column
+        conn.execute("CREATE TABLE api_keys (id INTEGER PRIMARY KEY, key TEXT NOT NULL UNIQUE, name TEXT NOT NULL,
+                      methods TEXT NOT NULL, rate_limit INTEGER NOT NULL, requests INTEGER NOT NULL DEFAULT 0,
+                      last_used TEXT NOT NULL DEFAULT '')", ()).unwrap();
+        conn.execute("INSERT INTO api_keys (key, name, methods, rate_limit) VALUES ('manual-key', 'manual', '', 0)", ()).unwrap();
+
+        create_api_keys_table(&conn).unwrap();
+        create_api_keys_table(&conn).unwrap();
+
+        let mut first  = ApiKey::new();
+        let mut second = ApiKey::new();
+
+        first.key  = "first-key".to_string();
+        second.key = "second-key".to_string();
+
+        save_api_key(&conn, &first).unwrap();
+        save_api_key(&conn, &second).unwrap();
+
+        // The second key is removed from the config
+        remove_config_api_keys(&conn, &[first]).unwrap();
+
+        let keys: Vec<String> = read_api_keys(&conn).unwrap().into_iter().map(|k| k.key).collect();
+
+        assert_eq!(keys, vec!["manual-key", "first-key"]);
+    }
+
+    #[test]
+    fn peers_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        create_peers_table(&conn).unwrap();
+
+        let mut peer = ConnectedNode::new();
+
+        peer.address     = "1.2.3.4:3414".to_string();
+        peer.user_agent  = "MW/Grin 5.3.3".to_string();
+        peer.bits        = 1 << 5;
+        peer.is_archival = true;
+        peer.reachable   = true;
+        peer.version     = 1000;
+        peer.difficulty  = 2000000000000000;
+        peer.genesis     = "40adad0aec27797b48840aa9e00472015c21baea118ce7a2ff1a82c0f8f5bf82".to_string();
+        peer.status      = "stale".to_string();
+        peer.location    = "Germany".to_string();
+        peer.flag        = "🇩🇪".to_string();
+        peer.isp         = "Hetzner Online GmbH".to_string();
+        peer.asn         = 24940;
+
+        save_peers(&conn, &[peer.clone()]).unwrap();
+
+        assert_eq!(read_peers(&conn).unwrap(), vec![peer]);
+    }
+
+    #[test]
+    fn peers_migration() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        conn.execute(
+            "CREATE TABLE peers (
+                id         INTEGER PRIMARY KEY,
+                address    TEXT NOT NULL UNIQUE,
+                user_agent TEXT NOT NULL,
+                bits       INTEGER NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen  INTEGER NOT NULL,
+                probes     INTEGER NOT NULL,
+                successes  INTEGER NOT NULL,
+                reachable  INTEGER NOT NULL
+            )", ()).unwrap();
+        conn.execute("INSERT INTO peers (address, user_agent, bits, first_seen, last_seen, probes, successes, reachable)
+                      VALUES ('1.2.3.4:3414', 'MW/Grin 5.3.3', 1, 10, 20, 3, 2, 1)", ()).unwrap();
+
+        // Running it twice must not fail on existing columns
+        create_peers_table(&conn).unwrap();
+        create_peers_table(&conn).unwrap();
+
+        let peers = read_peers(&conn).unwrap();
+
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].address, "1.2.3.4:3414");
+        assert_eq!(peers[0].version, 0);
+        assert_eq!(peers[0].status, "");
+
+        let mut peer = peers[0].clone();
+
+        peer.version = 1000;
+        peer.status  = "stale".to_string();
+
+        save_peers(&conn, &[peer.clone()]).unwrap();
+
+        assert_eq!(read_peers(&conn).unwrap(), vec![peer]);
+    }
+}
@@ -2,83 +2,338 @@ use config::Config;
 use std::fs;
 use lazy_static::lazy_static;
 
-use crate::data::ExplorerConfig;
+use crate::data::{ApiKey, ExplorerConfig, PriceSource, Upstream};
 
 
 // Static explorer config structure
 lazy_static! {
-    pub static ref CONFIG: ExplorerConfig = {
-        let mut cfg = ExplorerConfig::new();
-        let toml    = Config::builder().add_source(config::File::with_name("Explorer")).build().unwrap();
-
-        // Mandatory settings
-        cfg.host          = toml.get_string("host").unwrap();
-        cfg.proto         = toml.get_string("proto").unwrap();
-        cfg.coingecko_api = toml.get_string("coingecko_api").unwrap();
-        cfg.public_api    = toml.get_string("public_api").unwrap();
-        
-        // Optional settings
-        match toml.get_string("port") {
-            Ok(v)   => cfg.port = v,
-            Err(_e) => {},
-        }
-        
-        match toml.get_string("user") {
-            Ok(v)   => cfg.user = v,
-            Err(_e) => {},
-        }
-        
-        match toml.get_string("api_secret_path") {
-            Ok(v)   => cfg.api_secret_path = v,
-            Err(_e) => {},
-        }
-        
-        match toml.get_string("foreign_api_secret_path") {
-            Ok(v)   => cfg.foreign_api_secret_path = v,
-            Err(_e) => {},
-        }
-        
-        match toml.get_string("grin_dir") {
-            Ok(v)   => cfg.grin_dir = v,
-            Err(_e) => {},
-        }
-       
-        match toml.get_array("stats_source") {
-            Ok(nodes)   => {
-                               for endpoint in nodes.clone() {
-                                   cfg.stats_source.push(endpoint.into_string().unwrap());
-                               }
-                           },
-            Err(_e) => {},
-        }
-       
-        match toml.get_array("public_nodes") {
-            Ok(nodes)   => {
-                               for endpoint in nodes.clone() {
-                                   cfg.public_nodes.push(endpoint.into_string().unwrap());
+    pub static ref CONFIG: ExplorerConfig = load("Explorer");
+}
+
+
+// Loading explorer config from a file.
+pub fn load(name: &str) -> ExplorerConfig {
+    let mut cfg = ExplorerConfig::new();
+    let toml    = Config::builder().add_source(config::File::with_name(name)).build().unwrap();
+
+    // Mandatory settings
+    cfg.host          = toml.get_string("host").unwrap();
+    cfg.proto         = toml.get_string("proto").unwrap();
+    cfg.coingecko_api = toml.get_string("coingecko_api").unwrap();
+    cfg.public_api    = toml.get_string("public_api").unwrap();
+    
+    // Optional settings
+    match toml.get_string("port") {
+        Ok(v)   => cfg.port = v,
+        Err(_e) => {},
+    }
+    
+    match toml.get_string("user") {
+        Ok(v)   => cfg.user = v,
+        Err(_e) => {},
+    }
+    
+    match toml.get_string("api_secret_path") {
+        Ok(v)   => cfg.api_secret_path = v,
+        Err(_e) => {},
+    }
+    
+    match toml.get_string("foreign_api_secret_path") {
+        Ok(v)   => cfg.foreign_api_secret_path = v,
+        Err(_e) => {},
+    }
+    
+    match toml.get_string("grin_dir") {
+        Ok(v)   => cfg.grin_dir = v,
+        Err(_e) => {},
+    }
+   
+    match toml.get_array("stats_source") {
+        Ok(nodes)   => {
+                           for endpoint in nodes.clone() {
+                               cfg.stats_source.push(endpoint.into_string().unwrap());
+                           }
+                       },
+        Err(_e) => {},
+    }
+   
+    match toml.get_array("public_nodes") {
+        Ok(nodes)   => {
+                           for endpoint in nodes.clone() {
+                               cfg.public_nodes.push(endpoint.into_string().unwrap());
+                           }
+                       },
+        Err(_e) => {},
+    }
+
+    match toml.get_string("database") {
+        Ok(v)   => cfg.database = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("batch_limit") {
+        Ok(v)   => cfg.batch_limit = v as usize,
+        Err(_e) => {},
+    }
+
+    match toml.get_array("api_keys") {
+        Ok(keys) => {
+                        for entry in keys {
+                            let table   = entry.into_table().unwrap();
+                            let mut key = ApiKey::new();
+
+                            key.key  = table["key"].clone().into_string().unwrap();
+                            key.name = table["name"].clone().into_string().unwrap();
+
+                            if let Some(methods) = table.get("methods") {
+                                for method in methods.clone().into_array().unwrap() {
+                                    key.methods.push(method.into_string().unwrap());
+                                }
+                            }
+
+                            if let Some(limit) = table.get("rate_limit") {
+                                key.rate_limit = limit.clone().into_int().unwrap() as u32;
+                            }
+
+                            cfg.api_keys.push(key);
+                        }
+                    },
+        Err(_e) => {},
+    }
+
+    match toml.get_string("admin_token") {
+        Ok(v)   => cfg.admin_token = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_string("load_balance") {
+        Ok(v)   => cfg.load_balance = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("max_height_lag") {
+        Ok(v)   => cfg.max_height_lag = v as u64,
+        Err(_e) => {},
+    }
+
+    match toml.get_string("name") {
+        Ok(v)   => cfg.name = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_string("url") {
+        Ok(v)   => cfg.url = v.trim_end_matches('/').to_string(),
+        Err(_e) => {},
+    }
+
+    match toml.get_array("networks") {
+        Ok(files) => {
+                         for file in files {
+                             cfg.networks.push(file.into_string().unwrap());
+                         }
+                     },
+        Err(_e) => {},
+    }
+
+    match toml.get_int("http_timeout") {
+        Ok(v)   => cfg.http_timeout = v as u64,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("connect_timeout") {
+        Ok(v)   => cfg.connect_timeout = v as u64,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("http_retries") {
+        Ok(v)   => cfg.http_retries = v as u32,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("retry_backoff") {
+        Ok(v)   => cfg.retry_backoff = v as u64,
+        Err(_e) => {},
+    }
+
+    match toml.get_string("http_proxy") {
+        Ok(v)   => cfg.http_proxy = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_string("ca_cert_path") {
+        Ok(v)   => cfg.ca_cert_path = format!("{}", shellexpand::tilde(&v)),
+        Err(_e) => {},
+    }
+
+    match toml.get_int("probe_timeout") {
+        Ok(v)   => cfg.probe_timeout = v as u64,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("probe_concurrency") {
+        Ok(v)   => cfg.probe_concurrency = v as usize,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("crawl_interval") {
+        Ok(v)   => cfg.crawl_interval = v as u64,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("pubnode_interval") {
+        Ok(v)   => cfg.pubnode_interval = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_string("indexer") {
+        Ok(v)   => cfg.indexer = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_string("geoip_db") {
+        Ok(v)   => cfg.geoip_db = format!("{}", shellexpand::tilde(&v)),
+        Err(_e) => {},
+    }
+
+    match toml.get_string("geoip_asn_db") {
+        Ok(v)   => cfg.geoip_asn_db = format!("{}", shellexpand::tilde(&v)),
+        Err(_e) => {},
+    }
+
+    match toml.get_string("geoip_fallback") {
+        Ok(v)   => cfg.geoip_fallback = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("geoip_cache_ttl") {
+        Ok(v)   => cfg.geoip_cache_ttl = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_string("price_aggregation") {
+        Ok(v)   => cfg.price_aggregation = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("price_interval") {
+        Ok(v)   => cfg.price_interval = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_int("price_stale") {
+        Ok(v)   => cfg.price_stale = v,
+        Err(_e) => {},
+    }
+
+    match toml.get_array("currencies") {
+        Ok(codes) => {
+                         cfg.currencies = codes.into_iter().map(|c| c.into_string().unwrap().to_lowercase()).collect();
+                     },
+        Err(_e) => {},
+    }
+
+    match toml.get_array("price_sources") {
+        Ok(sources) => {
+                           for entry in sources {
+                               let table = entry.into_table().unwrap();
+                               let get   = |name: &str| match table.get(name) {
+                                   Some(v) => v.clone().into_string().unwrap(),
+                                   None    => String::new(),
+                               };
+                               let mut source = PriceSource::new();
+
+                               source.kind       = get("type");
+                               source.name       = get("name");
+                               source.url        = get("url");
+                               source.path       = format!("{}", shellexpand::tilde(&get("path")));
+                               source.usd        = get("usd");
+                               source.btc        = get("btc");
+                               source.volume_usd = get("volume_usd");
+                               source.volume_btc = get("volume_btc");
+
+                               if let Some(v) = table.get("priority") {
+                                   source.priority = v.clone().into_int().unwrap();
                                }
-                           },
-            Err(_e) => {},
-        }
-
-        match toml.get_string("database") {
-            Ok(v)   => cfg.database = v,
-            Err(_e) => {},
-        }
-
-        if cfg.api_secret_path.is_empty() == false {
-            cfg.api_secret = fs::read_to_string(format!("{}", shellexpand::tilde(&cfg.api_secret_path))).unwrap();
-        }
-
-        if cfg.foreign_api_secret_path.is_empty() == false {
-            cfg.foreign_api_secret = fs::read_to_string(format!("{}", shellexpand::tilde(&cfg.foreign_api_secret_path))).unwrap();
-        }
-
-        if cfg.grin_dir.is_empty() == false {
-            cfg.grin_dir = format!("{}", shellexpand::tilde(&cfg.grin_dir));
-        }
-        
-        cfg
-    };
+
+                               cfg.price_sources.push(source);
+                           }
+                       },
+        Err(_e) => {},
+    }
+
+    // CoinGecko is the default price source
+    if cfg.price_sources.is_empty() && cfg.coingecko_api == "enabled" {
+        let mut source = PriceSource::new();
+
+        source.kind = "coingecko".to_string();
+
+        cfg.price_sources.push(source);
+    }
+
+    cfg.price_sources.sort_by_key(|source| source.priority);
+
+    if cfg.api_secret_path.is_empty() == false {
+        cfg.api_secret = fs::read_to_string(format!("{}", shellexpand::tilde(&cfg.api_secret_path))).unwrap();
+    }
+
+    if cfg.foreign_api_secret_path.is_empty() == false {
+        cfg.foreign_api_secret = fs::read_to_string(format!("{}", shellexpand::tilde(&cfg.foreign_api_secret_path))).unwrap();
+    }
+
+    if cfg.grin_dir.is_empty() == false {
+        cfg.grin_dir = format!("{}", shellexpand::tilde(&cfg.grin_dir));
+    }
+
+    // The node defined above is the primary upstream
+    let mut primary = upstream(&cfg.host, &cfg.port, &cfg.proto, &cfg.user, "", "");
+
+    primary.api_secret         = cfg.api_secret.clone();
+    primary.foreign_api_secret = cfg.foreign_api_secret.clone();
+
+    cfg.upstreams.push(primary);
+
+    match toml.get_array("upstreams") {
+        Ok(nodes) => {
+                         for entry in nodes {
+                             let table = entry.into_table().unwrap();
+                             let get   = |name: &str| match table.get(name) {
+                                 Some(v) => v.clone().into_string().unwrap(),
+                                 None    => String::new(),
+                             };
+
+                             cfg.upstreams.push(upstream(&get("host"), &get("port"), &get("proto"), &get("user"),
+                                                         &get("api_secret_path"), &get("foreign_api_secret_path")));
+                         }
+                     },
+        Err(_e) => {},
+    }
+    
+    cfg
+}
+
+
+// Building upstream node from its settings.
+fn upstream(host: &str, port: &str, proto: &str, user: &str, api_secret_path: &str,
+            foreign_api_secret_path: &str) -> Upstream {
+    let mut node = Upstream::new();
+
+    if port.is_empty() {
+        node.name = host.to_string();
+    } else {
+        node.name = format!("{}:{}", host, port);
+    }
+
+    node.url  = format!("{}://{}", proto, node.name);
+    node.user = user.to_string();
+
+    if !api_secret_path.is_empty() {
+        node.api_secret = fs::read_to_string(format!("{}", shellexpand::tilde(api_secret_path))).unwrap();
+    }
+
+    if !foreign_api_secret_path.is_empty() {
+        node.foreign_api_secret = fs::read_to_string(format!("{}", shellexpand::tilde(foreign_api_secret_path))).unwrap();
+    }
+
+    node
 }
 
@@ -0,0 +1,123 @@
+use chrono::Utc;
+use lazy_static::lazy_static;
+use maxminddb::{geoip2, Reader};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use crate::data::GeoLocation;
+use crate::exconfig::CONFIG;
+use crate::http;
+
+
+lazy_static! {
+    // Country (or City) and ASN databases in MaxMind format
+    static ref COUNTRY_DB: Option<Reader<Vec<u8>>> = open(&CONFIG.geoip_db);
+    static ref ASN_DB:     Option<Reader<Vec<u8>>> = open(&CONFIG.geoip_asn_db);
+    // Lookup results by ip, with the lookup time
+    static ref CACHE:      Mutex<HashMap<IpAddr, (GeoLocation, i64)>> = Mutex::new(HashMap::new());
+}
+
+
+// Opening a database file, geolocation is skipped if the file is not configured or invalid.
+fn open(path: &str) -> Option<Reader<Vec<u8>>> {
+    if path.is_empty() {
+        return None;
+    }
+
+    match Reader::open_readfile(path) {
+        Ok(reader) => Some(reader),
+        Err(e)     => {
+                          error!("failed to open geoip database {}: {}", path, e);
+                          None
+                      },
+    }
+}
+
+
+// Looking up peer location.
+// Local databases are used first, ip-api.com is queried only if enabled as a fallback.
+pub async fn lookup(ip: IpAddr) -> Option<GeoLocation> {
+    let now = Utc::now().timestamp();
+
+    if let Some((location, time)) = CACHE.lock().unwrap().get(&ip) {
+        if now - time < CONFIG.geoip_cache_ttl {
+            return Some(location.clone());
+        }
+    }
+
+    let mut location = lookup_local(ip);
+
+    if location.is_none() && CONFIG.geoip_fallback == "enabled" {
+        location = lookup_remote(ip).await;
+    }
+
+    if let Some(location) = location.clone() {
+        CACHE.lock().unwrap().insert(ip, (location, now));
+    }
+
+    location
+}
+
+
+// Looking up local MaxMind databases.
+fn lookup_local(ip: IpAddr) -> Option<GeoLocation> {
+    let mut location = GeoLocation::new();
+    let mut found    = false;
+
+    if let Some(reader) = COUNTRY_DB.as_ref() {
+        if let Ok(record) = reader.lookup::<geoip2::Country>(ip) {
+            if let Some(country) = record.country {
+                location.country_code = country.iso_code.unwrap_or_default().to_string();
+                location.country      = country.names.and_then(|names| names.get("en").map(|n| n.to_string()))
+                                                     .unwrap_or_default();
+                found = true;
+            }
+        }
+    }
+
+    if let Some(reader) = ASN_DB.as_ref() {
+        if let Ok(record) = reader.lookup::<geoip2::Asn>(ip) {
+            location.asn = record.autonomous_system_number.unwrap_or_default();
+            location.isp = record.autonomous_system_organization.unwrap_or_default().to_string();
+            found = true;
+        }
+    }
+
+    if found {
+        Some(location)
+    } else {
+        None
+    }
+}
+
+
+// Looking up ip-api.com, note that peer addresses are sent to a third party.
+async fn lookup_remote(ip: IpAddr) -> Option<GeoLocation> {
+    let url = format!("http://ip-api.com/json/{}?fields=status,country,countryCode,isp,as", ip);
+
+    let resp: Value = match http::send(http::CLIENT.get(&url), true).await {
+        Ok(resp) => resp.json().await.ok()?,
+        Err(e)   => {
+                        warn!("geoip fallback failed: {}", e);
+                        return None;
+                    },
+    };
+
+    if resp["status"] != "success" {
+        return None;
+    }
+
+    let mut location = GeoLocation::new();
+
+    location.country      = resp["country"].as_str().unwrap_or_default().to_string();
+    location.country_code = resp["countryCode"].as_str().unwrap_or_default().to_string();
+    location.isp          = resp["isp"].as_str().unwrap_or_default().to_string();
+    // "AS24940 Hetzner Online GmbH"
+    location.asn          = resp["as"].as_str().unwrap_or_default()
+                                      .split_whitespace().next().unwrap_or_default()
+                                      .trim_start_matches("AS").parse().unwrap_or_default();
+
+    Some(location)
+}
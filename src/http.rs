@@ -0,0 +1,68 @@
+use lazy_static::lazy_static;
+use reqwest::{Certificate, Client, Proxy, RequestBuilder, Response};
+use rocket::tokio;
+use std::fs;
+use std::time::Duration;
+
+use crate::exconfig::CONFIG;
+
+
+// Shared HTTP client, keeps connections to the nodes and external APIs alive between calls.
+lazy_static! {
+    pub static ref CLIENT: Client = build();
+}
+
+
+// Building HTTP client from the explorer config.
+fn build() -> Client {
+    let mut builder = Client::builder()
+                          .timeout(Duration::from_secs(CONFIG.http_timeout))
+                          .connect_timeout(Duration::from_secs(CONFIG.connect_timeout))
+                          .pool_idle_timeout(Duration::from_secs(90))
+                          .user_agent("https://github.com/aglkm/grin-explorer");
+
+    if !CONFIG.http_proxy.is_empty() {
+        builder = builder.proxy(Proxy::all(&CONFIG.http_proxy).expect("invalid http_proxy"));
+    }
+
+    if !CONFIG.ca_cert_path.is_empty() {
+        let pem  = fs::read(&CONFIG.ca_cert_path).expect("failed to read ca_cert_path");
+        let cert = Certificate::from_pem(&pem).expect("invalid ca certificate");
+
+        builder = builder.add_root_certificate(cert);
+    }
+
+    builder.build().expect("failed to build http client")
+}
+
+
+// Sending a request, idempotent requests are retried with exponential backoff
+// on connection errors, timeouts and 5xx responses.
+pub async fn send(request: RequestBuilder, idempotent: bool) -> Result<Response, reqwest::Error> {
+    let attempts = if idempotent { CONFIG.http_retries + 1 } else { 1 };
+    let mut n    = 0;
+
+    loop {
+        n += 1;
+
+        // Streaming bodies can't be cloned, send them once
+        let req = match request.try_clone() {
+            Some(req) if n < attempts => req,
+            _                         => return request.send().await,
+        };
+
+        match req.send().await {
+            Ok(resp) if !resp.status().is_server_error() => return Ok(resp),
+            Ok(resp) => warn!("http request failed, status code: {}, retrying", resp.status()),
+            Err(e)   => {
+                            if !e.is_connect() && !e.is_timeout() && !e.is_request() {
+                                return Err(e);
+                            }
+                            warn!("http request failed: {}, retrying", e);
+                        },
+        }
+
+        tokio::time::sleep(Duration::from_millis(CONFIG.retry_backoff * 2u64.pow(n - 1))).await;
+    }
+}
+
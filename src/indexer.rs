@@ -0,0 +1,349 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use serde_json::Value;
+
+use crate::data::{BlockTime, IndexedBlock, Output, SearchResult};
+use crate::database;
+use crate::networks::Network;
+use crate::requests;
+
+
+// Number of blocks requested at once.
+const BATCH: u64 = 100;
+// Minimum length of a hex prefix to search for.
+pub const MIN_PREFIX: usize = 4;
+// Maximum number of matches of each type.
+const MAX_RESULTS: u32 = 20;
+// Maximum number of blocks in a time range, a month of blocks.
+pub const MAX_RANGE: u64 = 31 * 1440;
+// Maximum number of blocks in a time range read from the node when the index doesn't cover it.
+pub const MAX_NODE_RANGE: u64 = 1440;
+
+
+// Whether the chain index is available.
+pub fn enabled(net: &Network) -> bool {
+    net.config.indexer == "enabled" && !net.config.database.is_empty()
+}
+
+
+// Reading block data to index from get_blocks result.
+fn parse_block(block: &Value) -> Option<IndexedBlock> {
+    let header = &block["header"];
+
+    Some(IndexedBlock {
+        height:    header["height"].as_u64()?,
+        hash:      header["hash"].as_str()?.to_string(),
+        timestamp: DateTime::parse_from_rfc3339(header["timestamp"].as_str()?).ok()?.timestamp(),
+        kernels:   block["kernels"].as_array()?.iter()
+                   .map(|k| (k["excess"].as_str().unwrap_or_default().to_string(),
+                             k["features"].as_str().unwrap_or_default().to_string(),
+                             k["fee"].as_u64().unwrap_or_default(),
+                             k["lock_height"].as_u64().unwrap_or_default()))
+                   .collect(),
+        outputs:   block["outputs"].as_array()?.iter()
+                   .map(|o| (o["commit"].as_str().unwrap_or_default().to_string(),
+                             o["output_type"].as_str().unwrap_or_default().to_string(),
+                             o["mmr_index"].as_u64().unwrap_or_default()))
+                   .collect(),
+        inputs:    block["inputs"].as_array()?.iter()
+                   .map(|i| i.as_str().unwrap_or_default().to_string())
+                   .collect(),
+    })
+}
+
+
+// Hash of a block at the height, from the node.
+async fn get_hash(net: &Network, height: u64) -> Result<String, anyhow::Error> {
+    let params = &format!("[{}, null, null]", height)[..];
+    let resp   = requests::call(net, "get_header", params, "1", "foreign").await?;
+
+    match resp["result"]["Ok"]["hash"].as_str() {
+        Some(hash) => Ok(hash.to_string()),
+        None       => bail!("header {} not found", height),
+    }
+}
+
+
+// Indexing the next batch of blocks, returns false once the index has caught up with the node.
+// Blocks replaced by a reorg are removed from the index first.
+pub async fn update(net: &Network) -> Result<bool, anyhow::Error> {
+    let tip = match net.dash.lock().unwrap().height.parse::<u64>() {
+        Ok(height) => height,
+        Err(_)     => return Ok(false),
+    };
+
+    let conn      = database::open_db_connection(&net.config.database)?;
+    let mut start = 0;
+
+    if let Some((mut height, mut hash)) = database::read_indexed_tip(&conn)? {
+        while get_hash(net, height).await? != hash {
+            warn!("reorg at height {}, reindexing ({}).", height, net.config.name);
+            database::delete_index_from(&conn, height)?;
+
+            match database::read_indexed_tip(&conn)? {
+                Some(tip) => (height, hash) = tip,
+                None      => break,
+            }
+        }
+
+        start = match database::read_indexed_tip(&conn)? {
+            Some((height, _)) => height + 1,
+            None              => 0,
+        };
+    }
+
+    if start > tip {
+        return Ok(false);
+    }
+
+    let end    = (start + BATCH - 1).min(tip);
+    let params = &format!("[{}, {}, {}, false]", start, end, BATCH)[..];
+    let resp   = requests::call(net, "get_blocks", params, "1", "foreign").await?;
+    let blocks = match resp["result"]["Ok"]["blocks"].as_array() {
+        Some(blocks) => blocks.clone(),
+        None         => bail!("get_blocks {}-{} failed", start, end),
+    };
+
+    conn.execute("BEGIN", ())?;
+
+    for block in blocks.iter() {
+        match parse_block(block) {
+            Some(block) => database::save_indexed_block(&conn, &block)?,
+            None        => {
+                               conn.execute("ROLLBACK", ())?;
+                               bail!("invalid block in get_blocks {}-{}", start, end);
+                           },
+        }
+    }
+
+    conn.execute("COMMIT", ())?;
+
+    if end == tip {
+        info!("indexed up to {} ({}).", tip, net.config.name);
+    }
+
+    Ok(end < tip)
+}
+
+
+// Searching block hashes, kernel excesses and output commitments by hex prefix.
+pub fn search(net: &Network, prefix: &str) -> Vec<SearchResult> {
+    if !enabled(net) || prefix.len() < MIN_PREFIX || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Vec::new();
+    }
+
+    match database::open_db_connection(&net.config.database) {
+        Ok(conn) => database::search_index(&conn, prefix, MAX_RESULTS).unwrap_or_default(),
+        Err(_e)  => Vec::new(),
+    }
+}
+
+
+// Block at the height, from the index or the node.
+async fn get_block_time(net: &Network, height: u64) -> Option<BlockTime> {
+    if enabled(net) {
+        if let Ok(conn) = database::open_db_connection(&net.config.database) {
+            if let Ok(Some(block)) = database::read_block_time(&conn, height) {
+                return Some(block);
+            }
+        }
+    }
+
+    let params = &format!("[{}, null, null]", height)[..];
+    let resp   = requests::call(net, "get_header", params, "1", "foreign").await.ok()?;
+    let header = &resp["result"]["Ok"];
+
+    Some(BlockTime {
+        height,
+        hash:      header["hash"].as_str()?.to_string(),
+        timestamp: DateTime::parse_from_rfc3339(header["timestamp"].as_str()?).ok()?.timestamp(),
+    })
+}
+
+
+// Height of the first block at or after the timestamp from the index only, None if the index doesn't cover it.
+pub fn indexed_block_at(net: &Network, timestamp: i64) -> Option<u64> {
+    if !enabled(net) {
+        return None;
+    }
+
+    let conn = database::open_db_connection(&net.config.database).ok()?;
+
+    database::read_block_at(&conn, timestamp).ok()?
+}
+
+
+// Height of the first block at or after the timestamp, None if there is no such block yet.
+// The index is used if it covers the time, otherwise the node headers are searched.
+pub async fn block_at(net: &Network, timestamp: i64) -> Option<u64> {
+    if let Some(height) = indexed_block_at(net, timestamp) {
+        return Some(height);
+    }
+
+    let tip = net.dash.lock().unwrap().height.parse::<u64>().ok()?;
+
+    if get_block_time(net, tip).await?.timestamp < timestamp {
+        return None;
+    }
+
+    // Block timestamps are strictly increasing
+    let mut low  = 0;
+    let mut high = tip;
+
+    while low < high {
+        let mid = (low + high) / 2;
+
+        if get_block_time(net, mid).await?.timestamp < timestamp {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    Some(low)
+}
+
+
+// Parsing time as unix seconds, RFC 3339, "YYYY-MM-DD HH:MM:SS" or "YYYY-MM-DD" (UTC).
+pub fn parse_time(time: &str) -> Option<i64> {
+    let time = time.trim();
+
+    if let Ok(timestamp) = time.parse::<i64>() {
+        return Some(timestamp);
+    }
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(time) {
+        return Some(datetime.timestamp());
+    }
+
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S") {
+        return Some(datetime.and_utc().timestamp());
+    }
+
+    NaiveDate::parse_from_str(time, "%Y-%m-%d").ok()
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc().timestamp())
+}
+
+
+// Block with the timestamp closest to the given one.
+pub async fn closest_block(net: &Network, timestamp: i64) -> Option<BlockTime> {
+    let tip = net.dash.lock().unwrap().height.parse::<u64>().ok()?;
+
+    let after = match block_at(net, timestamp).await {
+        Some(height) => get_block_time(net, height).await?,
+        None         => return get_block_time(net, tip).await,
+    };
+
+    if after.height == 0 {
+        return Some(after);
+    }
+
+    let before = get_block_time(net, after.height - 1).await?;
+
+    if timestamp - before.timestamp <= after.timestamp - timestamp {
+        Some(before)
+    } else {
+        Some(after)
+    }
+}
+
+
+// Blocks with timestamps from (inclusive) to (exclusive).
+pub async fn blocks_between(net: &Network, from: i64, to: i64) -> Result<Vec<BlockTime>, anyhow::Error> {
+    let tip = match net.dash.lock().unwrap().height.parse::<u64>() {
+        Ok(height) => height,
+        Err(_)     => bail!("node is not synced"),
+    };
+
+    let start = match block_at(net, from).await {
+        Some(height) => height,
+        None         => return Ok(Vec::new()),
+    };
+    let end = match block_at(net, to).await {
+        Some(0)      => return Ok(Vec::new()),
+        Some(height) => height - 1,
+        None         => tip,
+    };
+
+    if start > end {
+        return Ok(Vec::new());
+    }
+
+    if end - start + 1 > MAX_RANGE {
+        bail!("range too large, max {} blocks", MAX_RANGE);
+    }
+
+    if enabled(net) {
+        let conn = database::open_db_connection(&net.config.database)?;
+
+        if let Some((indexed, _)) = database::read_indexed_tip(&conn)? {
+            if indexed >= end {
+                return Ok(database::read_block_times(&conn, start, end)?);
+            }
+        }
+    }
+
+    if end - start + 1 > MAX_NODE_RANGE {
+        bail!("range too large, max {} blocks without the chain index", MAX_NODE_RANGE);
+    }
+
+    let mut blocks = Vec::new();
+
+    for batch in (start..=end).step_by(BATCH as usize) {
+        let last   = (batch + BATCH - 1).min(end);
+        let params = &format!("[{}, {}, {}, false]", batch, last, BATCH)[..];
+        let resp   = requests::call(net, "get_blocks", params, "1", "foreign").await?;
+
+        for block in resp["result"]["Ok"]["blocks"].as_array().unwrap_or(&Vec::new()) {
+            if let Some(block) = parse_block(block) {
+                blocks.push(BlockTime { height: block.height, hash: block.hash, timestamp: block.timestamp });
+            }
+        }
+    }
+
+    Ok(blocks)
+}
+
+
+// Output from the chain index, also found when it is already spent.
+pub fn output(net: &Network, commit: &str) -> Option<Output> {
+    if !enabled(net) {
+        return None;
+    }
+
+    let conn = database::open_db_connection(&net.config.database).ok()?;
+
+    database::read_indexed_output(&conn, commit).ok()?
+}
+
+
+// Heights of the indexed kernels with the excess.
+pub fn kernel_heights(net: &Network, excess: &str) -> Vec<u64> {
+    if !enabled(net) {
+        return Vec::new();
+    }
+
+    match database::open_db_connection(&net.config.database) {
+        Ok(conn) => database::read_kernel_heights(&conn, excess).unwrap_or_default(),
+        Err(_e)  => Vec::new(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_formats() {
+        // Mainnet genesis, 2019-01-15 16:01:26 UTC
+        assert_eq!(parse_time("1547568086"), Some(1547568086));
+        assert_eq!(parse_time(" 1547568086 "), Some(1547568086));
+        assert_eq!(parse_time("2019-01-15T16:01:26Z"), Some(1547568086));
+        assert_eq!(parse_time("2019-01-15T18:01:26+02:00"), Some(1547568086));
+        assert_eq!(parse_time("2019-01-15 16:01:26"), Some(1547568086));
+        assert_eq!(parse_time("2019-01-15"), Some(1547568086 - 16 * 3600 - 60 - 26));
+        assert_eq!(parse_time("15-01-2019"), None);
+        assert_eq!(parse_time("2019-02-30"), None);
+        assert_eq!(parse_time(""), None);
+    }
+}
@@ -1,47 +1,75 @@
 #[macro_use] extern crate rocket;
-use chrono::Utc;
+#[macro_use] extern crate anyhow;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use either::Either;
+use futures::future::join_all;
+use humantime::format_duration;
 use num_format::{Locale, ToFormattedString};
-use rocket_dyn_templates::{Template, context};
+use rocket_dyn_templates::{Template, context, tera};
+use rocket::form::Form;
 use rocket::fs::FileServer;
+use rocket::http::{Cookie, CookieJar, Header, SameSite};
 use rocket::{State, tokio};
-use rocket::response::Redirect;
+use rocket::response::{Flash, Redirect};
+use rocket::request::FlashMessage;
 use rocket::serde::json::json;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use serde_json::Value;
 use tera_thousands::separate_with_commas;
 
-use crate::data::{Block, Dashboard, Kernel, NetStats, Output, Statistics, Transactions, OUTPUT_SIZE, KERNEL_SIZE};
+use crate::apikeys::{AdminToken, ApiKeyHeader};
+use crate::data::{ApiKey, Block, BlockTime, Kernel, Output, SearchResult, COINBASE_MATURITY, OUTPUT_SIZE, KERNEL_SIZE};
 use crate::exconfig::CONFIG;
+use crate::networks::{Net, Network, Networks};
+use crate::prices::Currency;
 
+mod apikeys;
+mod crawler;
 mod data;
 mod database;
 mod exconfig;
+mod geoip;
+mod http;
+mod indexer;
+mod mempool;
+mod networks;
+mod p2p;
+mod prices;
 mod requests;
+mod schedule;
+mod slatepack;
+mod upstream;
+mod verify;
+mod versions;
 mod worker;
 
 
 // Rendering main (Dashboard) page.
 #[get("/")]
-fn index(dashboard: &State<Arc<Mutex<Dashboard>>>) -> Template {
-    let data = dashboard.lock().unwrap();
+fn index(net: Net, cur: Currency) -> Template {
+    let data = net.dash.lock().unwrap();
 
     Template::render("index", context! {
         route:     "index",
+        network:   &net.config.name,
+        currency:  &cur.0,
         node_ver:  &data.node_ver,
         proto_ver: &data.proto_ver,
-        cg_api:    CONFIG.coingecko_api.clone(),
+        upstreams: net.config.upstreams.len(),
+        cg_api:    net.price_api(),
     })
 }
 
 
 // Rendering block list (Blocks) page.
 #[get("/block_list")]
-fn block_list() -> Template {
+fn block_list(net: Net) -> Template {
     Template::render("block_list", context! {
-        route:  "block_list",
-        cg_api: CONFIG.coingecko_api.clone(),
+        route:   "block_list",
+        network: &net.config.name,
+        cg_api:  net.price_api(),
     })
 }
 
@@ -49,12 +77,12 @@ fn block_list() -> Template {
 // Rendering block list starting with a specified height.
 // [<--] and [-->] buttons at the bottom of the block list (Blocks) page.
 #[get("/block_list/<input_height>")]
-async fn block_list_by_height(input_height: &str) -> Template {
+async fn block_list_by_height(input_height: &str, net: Net) -> Template {
     let mut blocks = Vec::<Block>::new();
     // Store current latest height
     let mut height = 0;
 
-    let _ = requests::get_block_list_by_height(&input_height, &mut blocks, &mut height).await;
+    let _ = requests::get_block_list_by_height(&net, &input_height, &mut blocks, &mut height).await;
 
     // Check if user's input doesn't overflow current height
     if blocks.is_empty() == false && blocks[0].height.is_empty() == false {
@@ -62,22 +90,25 @@ async fn block_list_by_height(input_height: &str) -> Template {
 
         if index >= height {
             Template::render("block_list", context! {
-                route:  "block_list",
-                cg_api: CONFIG.coingecko_api.clone(),
+                route:   "block_list",
+                network: &net.config.name,
+                cg_api:  net.price_api(),
             })
         } else {
             Template::render("block_list", context! {
-                route:  "block_list_by_height",
+                route:   "block_list_by_height",
+                network: &net.config.name,
                 index,
                 blocks,
                 height,
-                cg_api: CONFIG.coingecko_api.clone(),
+                cg_api:  net.price_api(),
             })
         }
     } else {
         Template::render("block_list", context! {
-            route:  "block_list",
-            cg_api: CONFIG.coingecko_api.clone(),
+            route:   "block_list",
+            network: &net.config.name,
+            cg_api:  net.price_api(),
         })
     }
 }
@@ -85,34 +116,63 @@ async fn block_list_by_height(input_height: &str) -> Template {
 
 // Rendering page for a specified block (by height).
 #[get("/block/<height>")]
-async fn block_details_by_height(height: &str) -> Template {
+async fn block_details_by_height(height: &str, net: Net, cur: Currency) -> Template {
     let mut block = Block::new();
 
     if height.is_empty() == false && height.chars().all(char::is_numeric) == true {
-        let _ = requests::get_block_data(&height, &mut block).await;
+        let _ = requests::get_block_data(&net, &height, &mut block).await;
 
         if block.height.is_empty() == false {
+            // Fees and reward valued at the price of the block day, or at the current price
+            let date      = block.time.get(..10).unwrap_or_default().to_string();
+            let mut price = None;
+            let mut day   = String::new();
+
+            if !net.config.database.is_empty() {
+                if let Ok(conn) = database::open_db_connection(&net.config.database) {
+                    price = database::read_price_on(&conn, &date).ok().flatten().and_then(|p| p.fiat_or_usd(&cur.0));
+                }
+            }
+
+            if price.is_some() {
+                day = date;
+            } else {
+                price = net.dash.lock().unwrap().fiat.get(&cur.0).copied();
+            }
+
+            let (fiat_fees, fiat_reward) = match price {
+                Some(price) => (format!("{} {:.4}", cur.symbol(), block.fees / 1000000000.0 * price),
+                                format!("{} {:.2}", cur.symbol(), block.reward * price)),
+                None        => (String::new(), String::new()),
+            };
+
             return Template::render("block_details", context! {
-                route:  "block_details",
+                route:       "block_details",
+                network:     &net.config.name,
                 block,
-                cg_api: CONFIG.coingecko_api.clone(),
+                fiat_fees,
+                fiat_reward,
+                price_day:   day,
+                currency:    &cur.0,
+                cg_api:      net.price_api(),
             });
         }
     }
 
     Template::render("error", context! {
-        route:  "error",
-        cg_api: CONFIG.coingecko_api.clone(),
+        route:   "error",
+        network: &net.config.name,
+        cg_api:  net.price_api(),
     })
 }
 
 
 // Rendering page for a specified block (by hash).
 #[get("/hash/<hash>")]
-async fn block_header_by_hash(hash: &str) -> Either<Template, Redirect> {
+async fn block_header_by_hash(hash: &str, net: Net) -> Either<Template, Redirect> {
     let mut height = String::new();
 
-    let _ = requests::get_block_header(&hash, &mut height).await;
+    let _ = requests::get_block_header(&net, &hash, &mut height).await;
 
     if hash.is_empty() == false {
         if height.is_empty() == false {
@@ -121,52 +181,114 @@ async fn block_header_by_hash(hash: &str) -> Either<Template, Redirect> {
     }
 
     return Either::Left(Template::render("error", context! {
-        route:  "error",
-        cg_api: CONFIG.coingecko_api.clone(),
+        route:   "error",
+        network: &net.config.name,
+        cg_api:  net.price_api(),
     }))
 }
 
 
 // Rendering page for a specified kernel.
 #[get("/kernel/<excess>")]
-async fn kernel(excess: &str) -> Template {
+async fn kernel(excess: &str, net: Net) -> Template {
     let mut kernel = Kernel::new();
 
-    let _ = requests::get_kernel(&excess, &mut kernel).await;
+    let _ = requests::get_kernel(&net, &excess, &mut kernel).await;
 
     if kernel.excess.is_empty() == false {
         return Template::render("kernel", context! {
-            route:  "kernel",
+            route:   "kernel",
+            network: &net.config.name,
             kernel,
-            cg_api: CONFIG.coingecko_api.clone(),
+            tx:      mempool::transaction(&net, excess).is_some(),
+            cg_api:  net.price_api(),
         })
     }
 
     Template::render("error", context! {
-        route:  "error",
-        cg_api: CONFIG.coingecko_api.clone(),
+        route:   "error",
+        network: &net.config.name,
+        cg_api:  net.price_api(),
+    })
+}
+
+
+// Rendering page of a transaction recorded from the mempool, by any of its kernels.
+#[get("/tx/<excess>")]
+fn transaction(excess: &str, net: Net) -> Template {
+    if let Some(tx) = mempool::transaction(&net, excess) {
+        let confirm_time = if tx.height > 0 {
+                               format_duration(Duration::from_secs((tx.confirmed - tx.seen).max(0) as u64)).to_string()
+                           } else {
+                               String::new()
+                           };
+
+        return Template::render("transaction", context! {
+            route:   "transaction",
+            network: &net.config.name,
+            tx,
+            confirm_time,
+            cg_api:  net.price_api(),
+        })
+    }
+
+    Template::render("error", context! {
+        route:   "error",
+        network: &net.config.name,
+        cg_api:  net.price_api(),
     })
 }
 
 
 // Rendering page for a specified output.
 #[get("/output/<commit>")]
-async fn output(commit: &str) -> Template {
+async fn output(commit: &str, net: Net) -> Template {
     let mut output = Output::new();
 
-    let _ = requests::get_output(&commit, &mut output).await;
+    let _ = requests::get_output(&net, &commit, &mut output).await;
 
     if output.commit.is_empty() == false {
         return Template::render("output", context! {
-            route:  "output",
+            route:   "output",
+            network: &net.config.name,
             output,
-            cg_api: CONFIG.coingecko_api.clone(),
+            cg_api:  net.price_api(),
         })
     }
 
     Template::render("error", context! {
-        route:  "error",
-        cg_api: CONFIG.coingecko_api.clone(),
+        route:   "error",
+        network: &net.config.name,
+        cg_api:  net.price_api(),
+    })
+}
+
+
+// Raw range proof of an output as a file download.
+#[derive(Responder)]
+#[response(content_type = "binary")]
+struct ProofFile {
+    data:        Vec<u8>,
+    disposition: Header<'static>,
+}
+
+#[get("/output/<commit>/proof")]
+async fn output_proof(commit: &str, net: Net) -> Option<ProofFile> {
+    let mut output = Output::new();
+
+    let _ = requests::get_output(&net, commit, &mut output).await;
+
+    if output.proof.is_empty() || !output.proof.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let data = (0..output.proof.len()).step_by(2)
+               .map(|i| u8::from_str_radix(&output.proof[i..i + 2], 16))
+               .collect::<Result<Vec<u8>, _>>().ok()?;
+
+    Some(ProofFile {
+        data,
+        disposition: Header::new("Content-Disposition", format!("attachment; filename=\"{}.proof\"", output.commit)),
     })
 }
 
@@ -175,28 +297,37 @@ async fn output(commit: &str) -> Template {
 // Using Option<&str> to match '/search' query without query params.
 // https://github.com/rwf2/Rocket/issues/608
 #[get("/search?<query>")]
-pub async fn search(query: Option<&str>) -> Either<Template, Redirect> {
+pub async fn search(query: Option<&str>, net: Net) -> Either<Template, Redirect> {
     // Unwrap Option and forward to Search page if no parameters
     let query = match query {
         Some(value) => value,
         None => return Either::Left(Template::render("search", context! {
-                           route:  "search",
-                           cg_api: CONFIG.coingecko_api.clone(),
+                           route:   "search",
+                           network: &net.config.name,
+                           cg_api:  net.price_api(),
                        })),
     };
 
     // Trim and lowercase the query
     let query = query.trim().to_lowercase();
 
+    // Date, first block of the day
+    if let Ok(date) = NaiveDate::parse_from_str(&query, "%Y-%m-%d") {
+        if let Some(height) = indexer::block_at(&net, date.and_time(NaiveTime::MIN).and_utc().timestamp()).await {
+            return Either::Right(Redirect::to(uri!(block_details_by_height(height.to_string()))));
+        }
+    }
+
     // Check for valid chars
     if query.chars().all(|x| (x >= 'a' && x <= 'f') || (x >= '0' && x <= '9')) == true {
 
-        // Block number
-        if query.chars().all(char::is_numeric) == true {
-            return Either::Right(Redirect::to(uri!(block_details_by_height(query))));
+        // Block number, if the block exists and the query doesn't look like a kernel or output prefix
+        let tip    = net.dash.lock().unwrap().height.parse::<u64>().unwrap_or_default();
+        let height = query.parse::<u64>().ok()
+                          .filter(|h| *h <= tip && !is_commitment_prefix(&query));
 
         // Block hash
-        } else if query.len() == 64 {
+        if query.len() == 64 {
             return Either::Right(Redirect::to(uri!(block_header_by_hash(query))));
             
         // Kernel or Unspent Output
@@ -209,7 +340,7 @@ pub async fn search(query: Option<&str>) -> Either<Template, Redirect> {
             let mut kernel = Kernel::new();
             let mut output = Output::new();
 
-            let _ = requests::get_kernel(&query, &mut kernel).await;
+            let _ = requests::get_kernel(&net, &query, &mut kernel).await;
 
             if kernel.excess.is_empty() == false {
                 // Here we are redirecting to kernel page and call get_kernel again there.
@@ -218,26 +349,81 @@ pub async fn search(query: Option<&str>) -> Either<Template, Redirect> {
                 return Either::Right(Redirect::to(uri!(kernel(query))));
             } else {
                 // If Kernel not found, then search for Unspent Output
-                let _ = requests::get_output(&query, &mut output).await;
+                let _ = requests::get_output(&net, &query, &mut output).await;
 
                 if output.commit.is_empty() == false {
                     return Either::Right(Redirect::to(uri!(output(query))));
                 }
             }
+
+        // Partial hash, kernel or output, digits-only prefixes are merged with the block number
+        } else {
+            let results = indexer::search(&net, &query);
+
+            if results.is_empty() && height.is_some() {
+                return Either::Right(Redirect::to(uri!(block_details_by_height(query))));
+            } else if results.len() == 1 && height.is_none() {
+                return Either::Right(Redirect::to(search_link(&results[0])));
+            } else if !results.is_empty() {
+                return Either::Left(Template::render("search_results", context! {
+                    route:   "search",
+                    network: &net.config.name,
+                    query,
+                    height,
+                    blocks:  results.iter().filter(|r| r.kind == "block").collect::<Vec<_>>(),
+                    kernels: results.iter().filter(|r| r.kind == "kernel").collect::<Vec<_>>(),
+                    outputs: results.iter().filter(|r| r.kind == "output").collect::<Vec<_>>(),
+                    cg_api:  net.price_api(),
+                }));
+            }
         }
     }
     
     Either::Left(Template::render("error", context! {
-        route:  "error",
-        cg_api: CONFIG.coingecko_api.clone(),
+        route:   "error",
+        network: &net.config.name,
+        cg_api:  net.price_api(),
     }))
 }
 
 
+// Kernel excesses and output commitments are Pedersen commitments, always starting with
+// 08 or 09, so a digits-only query with that prefix is not taken for a block number.
+fn is_commitment_prefix(query: &str) -> bool {
+    query.starts_with("08") || query.starts_with("09")
+}
+
+
+// Page of a search match.
+fn search_link(result: &SearchResult) -> String {
+    match result.kind.as_str() {
+        "block"  => format!("/hash/{}", result.id),
+        "kernel" => format!("/kernel/{}", result.id),
+        _        => format!("/output/{}", result.id),
+    }
+}
+
+
+// Search box suggestions: blocks, kernels and outputs matching the prefix.
+#[get("/search/suggest?<q>")]
+fn search_suggest(q: &str, net: Net) -> Value {
+    let query = q.trim().to_lowercase();
+
+    let results: Vec<Value> = indexer::search(&net, &query).iter().map(|r| json!({
+        "type":   r.kind,
+        "id":     r.id,
+        "height": r.height,
+        "url":    search_link(r),
+    })).collect();
+
+    json!(results)
+}
+
+
 // Rendering Statistics page.
 #[get("/stats")]
-fn stats(statistics: &State<Arc<Mutex<Statistics>>>) -> Template {
-    let data = statistics.lock().unwrap();
+fn stats(net: Net) -> Template {
+    let data = net.stats.lock().unwrap();
 
     // Get the length of our data vectors (all vectors are the same size)
     let len = data.date.len();
@@ -258,12 +444,23 @@ fn stats(statistics: &State<Arc<Mutex<Statistics>>>) -> Template {
         year = len - 365;
     }
 
+    // Fees as share of daily miner revenue (block rewards and fees), percent
+    let fee_share: Vec<String> = data.fees.iter().map(|fees| match fees.parse::<f64>() {
+        Ok(fees) => format!("{:.3}", fees / ((schedule::BLOCK_REWARD * (86400 / schedule::BLOCK_TIME)) as f64 + fees) * 100.0),
+        Err(_)   => "null".to_string(),
+    }).collect();
+
     let mut m_date     = data.date.clone();
     let mut m_hashrate = data.hashrate.clone();
     let mut m_txns     = data.txns.clone();
     let mut m_fees     = data.fees.clone();
     let mut m_utxos    = data.utxos.clone();
     let mut m_kernels  = data.kernels.clone();
+    let mut m_price    = data.price.clone();
+    let mut m_mcap     = data.mcap.clone();
+    let mut m_known    = data.peers_known.clone();
+    let mut m_reach    = data.peers_reach.clone();
+    let mut m_share    = fee_share.clone();
 
     // Get stats for a month period
     if month > 0 {  
@@ -273,6 +470,11 @@ fn stats(statistics: &State<Arc<Mutex<Statistics>>>) -> Template {
         m_fees     = data.fees.get(month..).unwrap().to_vec();
         m_utxos    = data.utxos.get(month..).unwrap().to_vec();
         m_kernels  = data.kernels.get(month..).unwrap().to_vec();
+        m_price    = data.price.get(month..).unwrap_or_default().to_vec();
+        m_mcap     = data.mcap.get(month..).unwrap_or_default().to_vec();
+        m_known    = data.peers_known.get(month..).unwrap_or_default().to_vec();
+        m_reach    = data.peers_reach.get(month..).unwrap_or_default().to_vec();
+        m_share    = fee_share.get(month..).unwrap_or_default().to_vec();
     }
 
     let mut sm_date     = data.date.clone();
@@ -281,6 +483,11 @@ fn stats(statistics: &State<Arc<Mutex<Statistics>>>) -> Template {
     let mut sm_fees     = data.fees.clone();
     let mut sm_utxos    = data.utxos.clone();
     let mut sm_kernels  = data.kernels.clone();
+    let mut sm_price    = data.price.clone();
+    let mut sm_mcap     = data.mcap.clone();
+    let mut sm_known    = data.peers_known.clone();
+    let mut sm_reach    = data.peers_reach.clone();
+    let mut sm_share    = fee_share.clone();
 
     // Get stats for six months period
     if six_months > 0 {  
@@ -290,6 +497,11 @@ fn stats(statistics: &State<Arc<Mutex<Statistics>>>) -> Template {
         sm_fees     = data.fees.get(six_months..).unwrap().to_vec();
         sm_utxos    = data.utxos.get(six_months..).unwrap().to_vec();
         sm_kernels  = data.kernels.get(six_months..).unwrap().to_vec();
+        sm_price    = data.price.get(six_months..).unwrap_or_default().to_vec();
+        sm_mcap     = data.mcap.get(six_months..).unwrap_or_default().to_vec();
+        sm_known    = data.peers_known.get(six_months..).unwrap_or_default().to_vec();
+        sm_reach    = data.peers_reach.get(six_months..).unwrap_or_default().to_vec();
+        sm_share    = fee_share.get(six_months..).unwrap_or_default().to_vec();
     }
         
     let mut y_date     = data.date.clone();
@@ -298,6 +510,11 @@ fn stats(statistics: &State<Arc<Mutex<Statistics>>>) -> Template {
     let mut y_fees     = data.fees.clone();
     let mut y_utxos    = data.utxos.clone();
     let mut y_kernels  = data.kernels.clone();
+    let mut y_price    = data.price.clone();
+    let mut y_mcap     = data.mcap.clone();
+    let mut y_known    = data.peers_known.clone();
+    let mut y_reach    = data.peers_reach.clone();
+    let mut y_share    = fee_share.clone();
         
     // Get stats for a year period
     if year > 0 {  
@@ -307,199 +524,640 @@ fn stats(statistics: &State<Arc<Mutex<Statistics>>>) -> Template {
         y_fees     = data.fees.get(year..).unwrap().to_vec();
         y_utxos    = data.utxos.get(year..).unwrap().to_vec();
         y_kernels  = data.kernels.get(year..).unwrap().to_vec();
+        y_price    = data.price.get(year..).unwrap_or_default().to_vec();
+        y_mcap     = data.mcap.get(year..).unwrap_or_default().to_vec();
+        y_known    = data.peers_known.get(year..).unwrap_or_default().to_vec();
+        y_reach    = data.peers_reach.get(year..).unwrap_or_default().to_vec();
+        y_share    = fee_share.get(year..).unwrap_or_default().to_vec();
     }
 
     Template::render("stats", context! {
-        route:      "stats",
-        user_agent: data.user_agent.clone(),
-        count:      data.count.clone(),
-        total:      data.total,
-        date:       data.date.clone(),
-        hashrate:   data.hashrate.clone(),
-        txns:       data.txns.clone(),
-        fees:       data.fees.clone(),
-        utxos:      data.utxos.clone(),
-        kernels:    data.kernels.clone(),
+        route:       "stats",
+        network:     &net.config.name,
+        user_agent:  data.user_agent.clone(),
+        count:       data.count.clone(),
+        total:       data.total,
+        date:        data.date.clone(),
+        hashrate:    data.hashrate.clone(),
+        txns:        data.txns.clone(),
+        fees:        data.fees.clone(),
+        utxos:       data.utxos.clone(),
+        kernels:     data.kernels.clone(),
+        price:       data.price.clone(),
+        mcap:        data.mcap.clone(),
+        known:       data.peers_known.clone(),
+        reach:       data.peers_reach.clone(),
+        share:       fee_share,
+        adoption:    versions::chart(&data.versions, "user_agent"),
+        caps:        versions::chart(&data.versions, "capability"),
         m_date,
         m_hashrate,
         m_txns,
         m_fees,
         m_utxos,
         m_kernels,
+        m_price,
+        m_mcap,
+        m_known,
+        m_reach,
+        m_share,
         sm_date,
         sm_hashrate,
         sm_txns,
         sm_fees,
         sm_utxos,
         sm_kernels,
+        sm_price,
+        sm_mcap,
+        sm_known,
+        sm_reach,
+        sm_share,
         y_date,
         y_hashrate,
         y_txns,
         y_fees,
         y_utxos,
         y_kernels,
+        y_price,
+        y_mcap,
+        y_known,
+        y_reach,
+        y_share,
         output_size: OUTPUT_SIZE,
         kernel_size: KERNEL_SIZE,
-        cg_api: CONFIG.coingecko_api.clone(),
+        cg_api:      net.price_api(),
+    })
+}
+
+
+// Rendering page of coinbase outputs that can't be spent yet.
+#[get("/coinbase")]
+fn coinbase(net: Net) -> Template {
+    // Tip is read first, stats worker locks dash before txns
+    let tip  = net.dash.lock().unwrap().height.parse::<u64>().unwrap_or_default();
+    let data = net.txns.lock().unwrap();
+
+    // Height, commitment, reward and blocks left
+    let outputs: Vec<(u64, String, f64, u64)> = data.coinbase.iter()
+        .map(|c| (c.0, c.1.clone(), c.2, COINBASE_MATURITY.saturating_sub(tip.saturating_sub(c.0) + 1)))
+        .collect();
+
+    Template::render("coinbase", context! {
+        route:    "coinbase",
+        network:  &net.config.name,
+        total:    outputs.iter().map(|c| c.2).sum::<f64>(),
+        outputs,
+        maturity: COINBASE_MATURITY,
+        cg_api:   net.price_api(),
     })
 }
 
 
 // Rendering Emission page.
 #[get("/emission")]
-fn emission(dashboard: &State<Arc<Mutex<Dashboard>>>) -> Template {
-    let data = dashboard.lock().unwrap();
+fn emission(net: Net, cur: Currency) -> Template {
+    let data = net.dash.lock().unwrap();
+    let tip  = schedule::Tip::new(data.height.parse::<u64>().unwrap_or(0), data.tip_time, &data.chain);
+    let rate = (schedule::BLOCK_REWARD / schedule::BLOCK_TIME) as f64;
 
-    let mut usd = 0.0;
-    let mut btc = 0.0;
+    // Past heights from the chain index, if it is built
+    let lookup = |time: i64| indexer::indexed_block_at(&net, time);
+
+    let mut fiat = None;
+    let mut btc  = 0.0;
 
     if data.price_usd.is_empty() == false && data.price_btc.is_empty() == false {
-        usd = data.price_usd.parse::<f64>().unwrap();
-        btc = data.price_btc.parse::<f64>().unwrap();
+        // Fiat values are hidden if there is no exchange rate for the display currency
+        fiat = cur.rate(&data).map(|r| data.price_usd.parse::<f64>().unwrap() * r);
+        btc  = data.price_btc.parse::<f64>().unwrap();
     }
 
+    let fiat_for = |secs: f64| fiat.map(|f| ((f * rate * secs) as u64).to_formatted_string(&Locale::en));
+
     Template::render("emission", context! {
-        route:      "emission",
-        cg_api:     CONFIG.coingecko_api.clone(),
-        usd_minute: format!("{:.2}", usd * 60.0),
-        usd_hour:   ((usd * 3600.0) as u64).to_formatted_string(&Locale::en),
-        usd_day:    ((usd * 86400.0) as u64).to_formatted_string(&Locale::en),
-        usd_week:   ((usd * 604800.0) as u64).to_formatted_string(&Locale::en),
-        usd_month:  ((usd * 2592000.0) as u64).to_formatted_string(&Locale::en),
-        usd_year:   ((usd * 31557600.0) as u64).to_formatted_string(&Locale::en),
-        btc_minute: format!("{:.8}", btc * 60.0),
-        btc_hour:   format!("{:.8}", btc * 3600.0),
-        btc_day:    format!("{:.8}", btc * 86400.0),
-        btc_week:   format!("{:.8}", btc * 604800.0),
-        btc_month:  format!("{:.8}", btc * 2592000.0),
-        btc_year:   format!("{:.8}", btc * 31557600.0),
+        route:       "emission",
+        network:     &net.config.name,
+        cg_api:      net.price_api(),
+        currency:    &cur.0,
+        symbol:      cur.symbol(),
+        fiat_minute: fiat.map(|f| format!("{:.2}", f * rate * 60.0)),
+        fiat_hour:   fiat_for(3600.0),
+        fiat_day:    fiat_for(86400.0),
+        fiat_week:   fiat_for(604800.0),
+        fiat_month:  fiat_for(2592000.0),
+        fiat_year:   fiat_for(31557600.0),
+        btc_minute:  format!("{:.8}", btc * rate * 60.0),
+        btc_hour:    format!("{:.8}", btc * rate * 3600.0),
+        btc_day:     format!("{:.8}", btc * rate * 86400.0),
+        btc_week:    format!("{:.8}", btc * rate * 604800.0),
+        btc_month:   format!("{:.8}", btc * rate * 2592000.0),
+        btc_year:    format!("{:.8}", btc * rate * 31557600.0),
+        curve:       schedule::curve(&tip, 10, &lookup),
+        years:       schedule::by_year(&tip, 100, &lookup),
+    })
+}
+
+
+// Supply at a height or date (YYYY-MM-DD), for exchanges and aggregators.
+// Without parameters returns the current supply.
+#[get("/api/v1/supply?<height>&<date>")]
+async fn api_supply(height: Option<u64>, date: Option<&str>, net: Net) -> Value {
+    let tip = {
+        let data = net.dash.lock().unwrap();
+
+        if data.height.is_empty() {
+            return json!({"error":"node is not synced"});
+        }
+
+        schedule::Tip::new(data.height.parse::<u64>().unwrap(), data.tip_time, &data.chain)
+    };
+
+    let point = match (height, date) {
+        (Some(height), _)  => schedule::at_height(&tip, height),
+        (None, Some(date)) => {
+                                  // Last block of a past day, from the chain
+                                  let last = match schedule::day_end(date) {
+                                      Some(time) if time < tip.time => indexer::block_at(&net, time + 1).await
+                                                                                .map(|h| h.saturating_sub(1)),
+                                      _                             => None,
+                                  };
+
+                                  match schedule::at_date(&tip, date, last) {
+                                      Some(point) => point,
+                                      None        => return json!({"error":"bad date, expected YYYY-MM-DD"}),
+                                  }
+                              },
+        (None, None)       => schedule::at_height(&tip, tip.height),
+    };
+
+    json!(point)
+}
+
+
+// Supply endpoints for aggregators (CoinMarketCap, CoinGecko), plain decimal text.
+// All coins are in circulation, there is no premine or locked supply.
+#[get("/api/v1/supply/total")]
+fn supply_total(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
+
+    match data.height.parse::<u64>() {
+        Ok(height) => schedule::supply(height).to_string(),
+        Err(_)     => String::new(),
+    }
+}
+
+
+#[get("/api/v1/supply/circulating")]
+fn supply_circulating(net: Net) -> String {
+    supply_total(net)
+}
+
+
+#[get("/api/v1/supply/soft")]
+fn supply_soft() -> String {
+    schedule::SOFT_SUPPLY.to_string()
+}
+
+
+// Daily node versions, protocol versions and capabilities of the reachable nodes.
+#[get("/api/v1/versions")]
+fn api_versions(net: Net) -> Value {
+    let data = net.stats.lock().unwrap();
+
+    versions::to_json(&data.versions)
+}
+
+
+// Block closest to a time (unix seconds, RFC 3339, "YYYY-MM-DD HH:MM:SS" or "YYYY-MM-DD", UTC).
+#[get("/block_at?<time>")]
+async fn block_at(time: &str, net: Net) -> Value {
+    let timestamp = match indexer::parse_time(time) {
+        Some(timestamp) => timestamp,
+        None            => return json!({"error":"bad time"}),
+    };
+
+    match indexer::closest_block(&net, timestamp).await {
+        Some(block) => block_time_json(&block),
+        None        => json!({"error":"block not found"}),
+    }
+}
+
+
+// Blocks with timestamps in a time range, from inclusive and to exclusive.
+#[get("/blocks?<from>&<to>")]
+async fn blocks_between(from: &str, to: &str, net: Net) -> Value {
+    let (from, to) = match (indexer::parse_time(from), indexer::parse_time(to)) {
+        (Some(from), Some(to)) => (from, to),
+        _                      => return json!({"error":"bad time"}),
+    };
+
+    match indexer::blocks_between(&net, from, to).await {
+        Ok(blocks) => json!({
+                          "from":   from,
+                          "to":     to,
+                          "count":  blocks.len(),
+                          "blocks": blocks.iter().map(block_time_json).collect::<Vec<Value>>(),
+                      }),
+        Err(e)     => json!({"error": e.to_string()}),
+    }
+}
+
+
+fn block_time_json(block: &BlockTime) -> Value {
+    json!({
+        "height":    block.height,
+        "hash":      block.hash,
+        "timestamp": block.timestamp,
+        "time":      DateTime::from_timestamp(block.timestamp, 0).map(|t| t.to_rfc3339()).unwrap_or_default(),
+    })
+}
+
+
+// Kernel signature verification against the kernel message.
+#[get("/api/v1/verify/kernel/<excess>")]
+async fn verify_kernel(excess: &str, net: Net) -> Value {
+    let mut kernel = Kernel::new();
+
+    let _ = requests::get_kernel(&net, excess, &mut kernel).await;
+
+    if kernel.excess.is_empty() {
+        return json!({"error":"kernel not found"});
+    }
+
+    json!({
+        "excess":   kernel.excess,
+        "height":   kernel.height.parse::<u64>().ok(),
+        "features": kernel.features,
+        "verified": kernel.verified,
+    })
+}
+
+
+// Block verification, kernel sums with the reward and block kernel offset, and kernel signatures.
+#[get("/api/v1/verify/block/<height>")]
+async fn verify_block(height: &str, net: Net) -> Value {
+    let mut block = Block::new();
+
+    if height.parse::<u64>().is_err() {
+        return json!({"error":"bad height"});
+    }
+
+    let _ = requests::get_block_data(&net, height, &mut block).await;
+
+    if block.height.is_empty() {
+        return json!({"error":"block not found"});
+    }
+
+    let _ = requests::get_block_sums(&net, height, &mut block).await;
+
+    json!({
+        "height":           block.height.parse::<u64>().ok(),
+        "hash":             block.hash,
+        "kernels":          block.ker_len,
+        "valid_signatures": block.sigs_ok,
+        "kernel_sums":      block.sums_ok,
+        "verified":         block.sums_ok.map(|ok| ok && block.sigs_ok == block.ker_len),
+    })
+}
+
+
+// Payment proof verification form fields, the kernel height is optional.
+#[derive(FromForm)]
+struct ProofForm<'r> {
+    proof:  &'r str,
+    height: Option<u64>,
+}
+
+
+// Rendering Payment Proof page.
+#[get("/payment_proof")]
+fn payment_proof_form(net: Net) -> Template {
+    Template::render("payment_proof", context! {
+        route:   "payment_proof",
+        network: &net.config.name,
+        cg_api:  net.price_api(),
+    })
+}
+
+
+// Rendering Payment Proof page with the verification result of a pasted proof.
+#[post("/payment_proof", data = "<form>")]
+async fn payment_proof(form: Form<ProofForm<'_>>, net: Net) -> Template {
+    let (result, error) = match verify::payment_proof(&net, form.proof, form.height).await {
+        Ok(result) => (Some(result), String::new()),
+        Err(e)     => (None, e.to_string()),
+    };
+
+    Template::render("payment_proof", context! {
+        route:   "payment_proof",
+        network: &net.config.name,
+        proof:   form.proof,
+        height:  form.height,
+        result,
+        error,
+        cg_api:  net.price_api(),
+    })
+}
+
+
+// Payment proof verification, the proof JSON is the request body.
+#[post("/api/v1/verify/payment_proof?<height>", data = "<data>")]
+async fn verify_payment_proof(data: &str, height: Option<u64>, net: Net) -> Value {
+    match verify::payment_proof(&net, data, height).await {
+        Ok(result) => json!(result),
+        Err(e)     => json!({"error": e.to_string()}),
+    }
+}
+
+
+// Transaction broadcast form fields, action is either "decode" or "push".
+#[derive(FromForm)]
+struct BroadcastForm<'r> {
+    tx:     &'r str,
+    action: &'r str,
+    fluff:  bool,
+}
+
+
+// Rendering Broadcast page.
+#[get("/broadcast")]
+fn broadcast_form(net: Net) -> Template {
+    Template::render("broadcast", context! {
+        route:    "broadcast",
+        network:  &net.config.name,
+        can_push: apikeys::is_public("push_transaction", "foreign"),
+        cg_api:   net.price_api(),
+    })
+}
+
+
+// Rendering Broadcast page with the decoded transaction, pushing it to the node if requested.
+#[post("/broadcast", data = "<form>")]
+async fn broadcast(form: Form<BroadcastForm<'_>>, net: Net) -> Template {
+    let can_push   = apikeys::is_public("push_transaction", "foreign");
+    let mut error  = String::new();
+    let mut pushed = false;
+    let mut tx     = None;
+    let mut raw    = String::new();
+
+    match slatepack::decode(form.tx) {
+        Ok(value) => {
+            tx  = mempool::parse_entry(&json!({"tx": value}), Utc::now().timestamp(), 0);
+            raw = serde_json::to_string_pretty(&value).unwrap_or_default();
+
+            if let Err(e) = slatepack::validate(&value) {
+                error = e.to_string();
+            } else if form.action == "push" && can_push {
+                match requests::push_transaction(&net, &value, form.fluff).await {
+                    Ok(_)  => pushed = true,
+                    Err(e) => error = format!("node rejected the transaction: {}", e),
+                }
+            }
+        },
+        Err(e)    => error = e.to_string(),
+    }
+
+    Template::render("broadcast", context! {
+        route:    "broadcast",
+        network:  &net.config.name,
+        input:    form.tx,
+        fluff:    form.fluff,
+        can_push,
+        tx,
+        raw,
+        error,
+        pushed,
+        cg_api:   net.price_api(),
+    })
+}
+
+
+// All supply values in one JSON response.
+#[get("/api/v1/supply/json")]
+fn supply_json(net: Net) -> Value {
+    let data = net.dash.lock().unwrap();
+
+    let height = match data.height.parse::<u64>() {
+        Ok(height) => height,
+        Err(_)     => return json!({"error":"node is not synced"}),
+    };
+
+    let supply = schedule::supply(height);
+
+    json!({
+        "height":             height,
+        "total_supply":       supply,
+        "circulating_supply": supply,
+        "max_supply":         null,
+        "soft_supply":        schedule::SOFT_SUPPLY,
+        "soft_supply_pct":    supply as f64 / schedule::SOFT_SUPPLY as f64 * 100.0,
+        "inflation":          schedule::inflation(height),
     })
 }
 
 
 // Rendering Donate page.
 #[get("/donate")]
-fn donate() -> Template {
+fn donate(net: Net) -> Template {
     Template::render("donate", context! {
         route:      "donate",
+        network:    &net.config.name,
         public_api: CONFIG.public_api.clone(),
-        cg_api:     CONFIG.coingecko_api.clone(),
+        cg_api:     net.price_api(),
     })
 }
 
 
 // Rendering API Overview page.
 #[get("/api_overview")]
-fn api_overview() -> Template {
+fn api_overview(net: Net) -> Template {
     Template::render("api_overview", context! {
-        route:      "api_overview",
-        public_api: CONFIG.public_api.clone(),
-        cg_api:     CONFIG.coingecko_api.clone(),
+        route:       "api_overview",
+        network:     &net.config.name,
+        public_api:  CONFIG.public_api.clone(),
+        batch_limit: CONFIG.batch_limit,
+        cg_api:      net.price_api(),
     })
 }
 
 
 // Rendering Network page.
 #[get("/network")]
-fn network(netstats: &State<Arc<Mutex<NetStats>>>) -> Template {
-    let data = netstats.lock().unwrap();
+fn network(net: Net) -> Template {
+    let data = net.netstats.lock().unwrap();
 
     Template::render("network", context! {
-        route:  "network",
-        pub_nodes: &data.pub_nodes,
+        route:       "network",
+        network:     &net.config.name,
+        pub_nodes:   &data.pub_nodes,
         reach_nodes: &data.reach_nodes,
-        reach_len: &data.reach_nodes.len(),
-        cg_api: CONFIG.coingecko_api.clone(),
+        reach_len:   &data.reach_nodes.len(),
+        peers_len:   &data.peers.len(),
+        other_len:   data.peers.iter().filter(|p| p.status.ends_with("net")).count(),
+        nongrin_len: data.peers.iter().filter(|p| p.status == "non-grin").count(),
+        cg_api:      net.price_api(),
     })
 }
 
 
 // Owner API.
-// Whitelisted methods: get_connected_peers, get_peers, get_status.
+// Public methods: get_connected_peers, get_peers, get_status.
+// Other methods are available with an API key passed via 'X-API-Key' header.
+// Accepts a single JSON-RPC request or a batch (array) of requests.
 #[post("/v2/owner", data="<data>")]
-async fn api_owner(data: &str) -> Value {
-    if CONFIG.public_api == "enabled" {
-        let result = serde_json::from_str(data);
-
-        let v: Value = match result {
-            Ok(value) => value,
-            Err(_err) => return json!({"error":"bad syntax"}),
-        };
-
-        let method = match v["method"].as_str() {
-            Some(value) => value,
-            _ => return json!({"error":"bad syntax"}),
-        };
-    
-        if method == "get_connected_peers" || method == "get_peers" || method == "get_status" {
-            let resp = requests::call(method, v["params"].to_string().as_str(), v["id"].to_string().as_str(), "owner").await;
+async fn api_owner(data: &str, key: ApiKeyHeader<'_>, keys: &State<Arc<Mutex<Vec<ApiKey>>>>, net: Net) -> Value {
+    forward_rpc(&net, data, "owner", key.0, keys).await
+}
 
-            let result = match resp {
-                Ok(value) => value,
-                Err(_err) => return json!({"error":"rpc call failed"}),
-            };
 
-            return json!(result);
-        }
+// Foreign API.
+// All methods are public.
+// Accepts a single JSON-RPC request or a batch (array) of requests.
+#[post("/v2/foreign", data="<data>")]
+async fn api_foreign(data: &str, key: ApiKeyHeader<'_>, keys: &State<Arc<Mutex<Vec<ApiKey>>>>, net: Net) -> Value {
+    forward_rpc(&net, data, "foreign", key.0, keys).await
+}
 
-        json!({"error":"not allowed"})
-    } else {
-        json!({"error":"not allowed"})
+
+// Parsing incoming JSON-RPC data and forwarding it to the node.
+// Batch entries are forwarded in parallel, responses are returned in the order of the requests.
+async fn forward_rpc(net: &Network, data: &str, rpc_type: &str, key: Option<&str>, keys: &Arc<Mutex<Vec<ApiKey>>>) -> Value {
+    let result = serde_json::from_str(data);
+
+    let v: Value = match result {
+        Ok(value) => value,
+        Err(_err) => return rpc_error(&Value::Null, -32700, "bad syntax"),
+    };
+
+    match v.as_array() {
+        Some(batch) => {
+            if batch.is_empty() || batch.len() > CONFIG.batch_limit {
+                return rpc_error(&Value::Null, -32600, "bad batch size");
+            }
+
+            let resp = join_all(batch.iter().map(|req| forward_request(net, req, rpc_type, key, keys))).await;
+
+            json!(resp)
+        },
+        None => forward_request(net, &v, rpc_type, key, keys).await,
     }
 }
 
 
-// Foreign API.
-// All methods are whitelisted.
-#[post("/v2/foreign", data="<data>")]
-async fn api_foreign(data: &str) -> Value {
-    if CONFIG.public_api == "enabled" {
-        let result = serde_json::from_str(data);
+// Forwarding a single JSON-RPC request to the node, checking access to the method first.
+async fn forward_request(net: &Network, v: &Value, rpc_type: &str, key: Option<&str>, keys: &Arc<Mutex<Vec<ApiKey>>>) -> Value {
+    let id = &v["id"];
+
+    let method = match v["method"].as_str() {
+        Some(value) => value,
+        _ => return rpc_error(id, -32600, "bad syntax"),
+    };
+
+    if let Err(err) = apikeys::authorize(keys, key, method, rpc_type) {
+        return rpc_error(id, -32000, err);
+    }
+
+    let resp = requests::call(net, method, v["params"].to_string().as_str(), id.to_string().as_str(), rpc_type).await;
 
-        let v: Value = match result {
-            Ok(value) => value,
-            Err(_err) => return json!({"error":"bad syntax"}),
-        };
+    match resp {
+        Ok(value) => value,
+        Err(_err) => rpc_error(id, -32603, "rpc call failed"),
+    }
+}
 
-        let method = match v["method"].as_str() {
-            Some(value) => value,
-            _ => return json!({"error":"bad syntax"}),
-        };
 
-        let resp = requests::call(method, v["params"].to_string().as_str(), v["id"].to_string().as_str(), "foreign").await;
+// JSON-RPC error object, echoing the request id (null if the request could not be read).
+fn rpc_error(id: &Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id":      id,
+        "error":   {"code": code, "message": message},
+    })
+}
 
-        let result = match resp {
-            Ok(value) => value,
-            Err(_err) => return json!({"error":"rpc call failed"}),
-        };
 
-        return json!(result);
-    } else {
-        json!({"error":"not allowed"})
+// Rendering API keys usage page.
+#[get("/admin/api_keys")]
+fn admin_api_keys(token: AdminToken, flash: Option<FlashMessage<'_>>, keys: &State<Arc<Mutex<Vec<ApiKey>>>>,
+                  net: Net) -> Template {
+    if CONFIG.admin_token.is_empty() {
+        return Template::render("error", context! {
+            route:   "error",
+            network: &net.config.name,
+            cg_api:  net.price_api(),
+        });
+    }
+
+    if !token.valid {
+        return Template::render("api_keys", context! {
+            route:   "api_keys",
+            network: &net.config.name,
+            login:   true,
+            failed:  token.attempted || flash.is_some(),
+            cg_api:  net.price_api(),
+        });
+    }
+
+    let mut data = keys.lock().unwrap().clone();
+
+    for key in data.iter_mut() {
+        key.key = apikeys::mask(&key.key);
+    }
+
+    Template::render("api_keys", context! {
+        route:   "api_keys",
+        network: &net.config.name,
+        keys:    data,
+        cg_api:  net.price_api(),
+    })
+}
+
+
+// Admin login form field.
+#[derive(FromForm)]
+struct AdminLogin<'r> {
+    token: &'r str,
+}
+
+
+// Starting an admin session, so the token is not passed in urls or kept in the cookie.
+#[post("/admin/login", data = "<form>")]
+fn admin_login(form: Form<AdminLogin<'_>>, cookies: &CookieJar<'_>) -> Either<Redirect, Flash<Redirect>> {
+    if !apikeys::is_admin(Some(form.token)) {
+        return Either::Right(Flash::error(Redirect::to(uri!(admin_api_keys)), "invalid token"));
     }
+
+    let cookie = Cookie::build(("admin_session", apikeys::new_session()))
+                 .path("/admin")
+                 .http_only(true)
+                 .same_site(SameSite::Strict);
+
+    cookies.add(cookie);
+
+    Either::Left(Redirect::to(uri!(admin_api_keys)))
 }
 
 
 // Start of HTMX routes.
 #[get("/rpc/peers/inbound")]
-fn peers_inbound(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn peers_inbound(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     data.inbound.to_string()
 }
 
 
 #[get("/rpc/peers/outbound")]
-fn peers_outbound(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn peers_outbound(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     data.outbound.to_string()
 }
 
 
 #[get("/rpc/sync/status")]
-fn sync_status(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn sync_status(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     if data.sync == "no_sync" {
         "Synced".to_string()
@@ -511,25 +1169,37 @@ fn sync_status(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
 }
 
 
+#[get("/rpc/upstream/active")]
+fn upstream_active(net: Net) -> String {
+    let node = upstream::active(&net);
+
+    if net.config.load_balance == "enabled" {
+        return format!("{} <i class='bi bi-shuffle' title='load balanced'></i>", node.name);
+    }
+
+    node.name
+}
+
+
 #[get("/rpc/market/supply")]
-fn market_supply(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn market_supply(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     format!("ツ {}", data.supply)
 }
 
 
 #[get("/rpc/market/supply_raw")]
-fn supply_raw(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn supply_raw(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     data.supply_raw.clone()
 }
 
 
 #[get("/rpc/market/soft_supply")]
-fn soft_supply(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn soft_supply(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     if data.supply.is_empty() == false {
         // 9 digits plus 2 commas, e.g. 168,038,400
@@ -546,40 +1216,40 @@ fn soft_supply(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
 
 
 #[get("/rpc/inflation/rate")]
-fn inflation_rate(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn inflation_rate(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     format!("{} %", data.inflation)
 }
 
 
 #[get("/rpc/market/volume_usd")]
-fn volume_usd(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn volume_usd(net: Net, cur: Currency) -> String {
+    let data = net.dash.lock().unwrap();
 
-    format!("$ {}", data.volume_usd)
+    cur.display(&data, &data.volume_usd, 0)
 }
 
 
 #[get("/rpc/market/volume_btc")]
-fn volume_btc(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn volume_btc(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     format!("₿ {}", data.volume_btc)
 }
 
 
 #[get("/rpc/price/usd")]
-fn price_usd(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn price_usd(net: Net, cur: Currency) -> String {
+    let data = net.dash.lock().unwrap();
 
-    format!("$ {}", data.price_usd)
+    cur.display(&data, &data.price_usd, 3)
 }
 
 
 #[get("/rpc/price/btc")]
-fn price_btc(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data       = dashboard.lock().unwrap();
+fn price_btc(net: Net) -> String {
+    let data       = net.dash.lock().unwrap();
     let trim: &[_] = &['0', '.'];
 
     format!("{} sats", data.price_btc.trim_start_matches(trim))
@@ -587,32 +1257,40 @@ fn price_btc(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
 
 
 #[get("/rpc/market/cap_usd")]
-fn mcap_usd(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn mcap_usd(net: Net, cur: Currency) -> String {
+    let data = net.dash.lock().unwrap();
 
-    format!("$ {}", data.cap_usd)
+    cur.display(&data, &data.cap_usd, 0)
 }
 
 
 #[get("/rpc/market/cap_btc")]
-fn mcap_btc(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn mcap_btc(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     format!("₿ {}", data.cap_btc)
 }
 
 
+#[get("/rpc/market/source")]
+fn price_source(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
+
+    data.price_source.clone()
+}
+
+
 #[get("/rpc/block/latest")]
-fn latest_height(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn latest_height(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     data.height.clone()
 }
 
 
 #[get("/rpc/block/time_since_last")]
-fn last_block_age(blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
-    let data = blocks.lock().unwrap();
+fn last_block_age(net: Net) -> String {
+    let data = net.blocks.lock().unwrap();
 
     if data.is_empty() == false {
         return data[0].time.clone();
@@ -623,8 +1301,8 @@ fn last_block_age(blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
 
 
 #[get("/rpc/disk/usage")]
-fn disk_usage(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn disk_usage(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     if data.disk_usage.is_empty() == false {
         return format!("{} GB", data.disk_usage);
@@ -635,24 +1313,24 @@ fn disk_usage(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
 
 
 #[get("/rpc/network/hashrate")]
-fn network_hashrate(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn network_hashrate(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     data.hashrate.clone()
 }
 
 
 #[get("/rpc/mining/production_cost")]
-fn production_cost(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn production_cost(net: Net, cur: Currency) -> String {
+    let data = net.dash.lock().unwrap();
 
-    format!("$ {}", data.production_cost)
+    cur.display(&data, &data.production_cost, 3)
 }
 
 
 #[get("/rpc/mining/reward_ratio")]
-fn reward_ratio(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn reward_ratio(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     if data.reward_ratio.is_empty() == false {
         let ratio = data.reward_ratio.parse::<f64>().unwrap();
@@ -673,56 +1351,56 @@ fn reward_ratio(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
 
 
 #[get("/rpc/mining/breakeven_cost")]
-fn breakeven_cost(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn breakeven_cost(net: Net, cur: Currency) -> String {
+    let data = net.dash.lock().unwrap();
 
-    format!("$ {} (kW/h)", data.breakeven_cost)
+    format!("{} (kW/h)", cur.display(&data, &data.breakeven_cost, 2))
 }
 
 
 #[get("/rpc/network/difficulty")]
-fn network_difficulty(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn network_difficulty(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     data.difficulty.to_string()
 }
 
 
 #[get("/rpc/mempool/txns")]
-fn mempool_txns(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn mempool_txns(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     data.txns.to_string()
 }
 
 
 #[get("/rpc/mempool/stem")]
-fn mempool_stem(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn mempool_stem(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     data.stem.to_string()
 }
 
 
 #[get("/rpc/txns/count_1h")]
-fn txns_count_1h(transactions: &State<Arc<Mutex<Transactions>>>) -> String {
-    let data = transactions.lock().unwrap();
+fn txns_count_1h(net: Net) -> String {
+    let data = net.txns.lock().unwrap();
 
     format!("{}, ツ {}", data.period_1h, data.fees_1h)
 }
 
 
 #[get("/rpc/txns/count_24h")]
-fn txns_count_24h(transactions: &State<Arc<Mutex<Transactions>>>) -> String {
-    let data = transactions.lock().unwrap();
+fn txns_count_24h(net: Net) -> String {
+    let data = net.txns.lock().unwrap();
 
     format!("{}, ツ {}", data.period_24h, data.fees_24h)
 }
 
 
 #[get("/rpc/block/link?<count>")]
-fn block_link(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
-    let data = blocks.lock().unwrap();
+fn block_link(count: usize, net: Net) -> String {
+    let data = net.blocks.lock().unwrap();
 
     if data.is_empty() == false && count < 10 {
         return format!("<a href=/block/{} class='text-decoration-none'>{}</a>",
@@ -734,8 +1412,8 @@ fn block_link(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
 
 
 #[get("/rpc/block/link_color?<count>")]
-fn block_link_color(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
-    let data = blocks.lock().unwrap();
+fn block_link_color(count: usize, net: Net) -> String {
+    let data = net.blocks.lock().unwrap();
 
     if data.is_empty() == false && count < 10 {
         return format!("<a href=/block/{} class='text-decoration-none darkorange-text'>{}</a>",
@@ -747,8 +1425,8 @@ fn block_link_color(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> Str
 
 
 #[get("/rpc/block/time?<count>")]
-fn block_time(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
-    let data = blocks.lock().unwrap();
+fn block_time(count: usize, net: Net) -> String {
+    let data = net.blocks.lock().unwrap();
 
     if data.is_empty() == false && count < 10 {
         return data[count].time.clone();
@@ -759,8 +1437,8 @@ fn block_time(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
 
 
 #[get("/rpc/block/kernels?<count>")]
-fn block_txns(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
-    let data = blocks.lock().unwrap();
+fn block_txns(count: usize, net: Net) -> String {
+    let data = net.blocks.lock().unwrap();
 
     if data.is_empty() == false && count < 10 {
         return data[count].ker_len.to_string();
@@ -771,8 +1449,8 @@ fn block_txns(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
 
 
 #[get("/rpc/block/inputs?<count>")]
-fn block_inputs(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
-    let data = blocks.lock().unwrap();
+fn block_inputs(count: usize, net: Net) -> String {
+    let data = net.blocks.lock().unwrap();
 
     if data.is_empty() == false && count < 10 {
         return data[count].in_len.to_string();
@@ -783,8 +1461,8 @@ fn block_inputs(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String
 
 
 #[get("/rpc/block/outputs?<count>")]
-fn block_outputs(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
-    let data = blocks.lock().unwrap();
+fn block_outputs(count: usize, net: Net) -> String {
+    let data = net.blocks.lock().unwrap();
 
     if data.is_empty() == false && count < 10 {
         return data[count].out_len.to_string();
@@ -795,8 +1473,8 @@ fn block_outputs(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String
 
 
 #[get("/rpc/block/fees?<count>")]
-fn block_fees(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
-    let data = blocks.lock().unwrap();
+fn block_fees(count: usize, net: Net) -> String {
+    let data = net.blocks.lock().unwrap();
 
     if data.is_empty() == false && count < 10 {
         return format!("ツ {}", data[count].fees / 1000000000.0);
@@ -807,8 +1485,8 @@ fn block_fees(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
 
 
 #[get("/rpc/block/size?<count>")]
-fn block_size(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
-    let data = blocks.lock().unwrap();
+fn block_size(count: usize, net: Net) -> String {
+    let data = net.blocks.lock().unwrap();
 
     if data.is_empty() == false && count < 10 {
         return data[count].size.clone();
@@ -819,8 +1497,8 @@ fn block_size(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
 
 
 #[get("/rpc/block/weight?<count>")]
-fn block_weight(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String {
-    let data = blocks.lock().unwrap();
+fn block_weight(count: usize, net: Net) -> String {
+    let data = net.blocks.lock().unwrap();
 
     if data.is_empty() == false && count < 10 {
         return format!("{} %", data[count].weight);
@@ -831,8 +1509,8 @@ fn block_weight(count: usize, blocks: &State<Arc<Mutex<Vec<Block>>>>) -> String
 
 
 #[get("/rpc/block_list/index")]
-fn block_list_index(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn block_list_index(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     if data.height.is_empty() == false && data.height.parse::<u64>().unwrap() > 10 {
         return format!("<a class='text-decoration-none' href='/block_list/{}'>
@@ -845,8 +1523,8 @@ fn block_list_index(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
 
 
 #[get("/rpc/blockchain/unspent_outputs")]
-fn unspent_outputs(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn unspent_outputs(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     if data.utxo_count.is_empty() == false {
         let utxo_count    = data.utxo_count.parse::<u64>().unwrap();
@@ -866,8 +1544,8 @@ fn unspent_outputs(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
 
 
 #[get("/rpc/blockchain/kernels")]
-fn kernels(dashboard: &State<Arc<Mutex<Dashboard>>>) -> String {
-    let data = dashboard.lock().unwrap();
+fn kernels(net: Net) -> String {
+    let data = net.dash.lock().unwrap();
 
     if data.kernel_mmr_size.is_empty() == false {
         let kernel_count    = data.kernel_mmr_size.parse::<u64>().unwrap() / 2;
@@ -894,111 +1572,172 @@ async fn main() {
     env_logger::init();
 
     info!("starting up.");
-    
-    let dash           = Arc::new(Mutex::new(Dashboard::new()));
-    let dash_clone     = dash.clone();
-    let blocks         = Arc::new(Mutex::new(Vec::<Block>::new()));
-    let blocks_clone   = blocks.clone();
-    let txns           = Arc::new(Mutex::new(Transactions::new()));
-    let txns_clone     = txns.clone();
-    let stats          = Arc::new(Mutex::new(Statistics::new()));
-    let stats_clone    = stats.clone();
-    let netstats       = Arc::new(Mutex::new(NetStats::new()));
-    let netstats_clone = netstats.clone();
-
-    let mut ready_data  = false;
-    let mut ready_stats = false;
-    let mut ready_db    = false;
-    let mut date        = "".to_string();
-    
-    // Initializing db and table
-    if CONFIG.database.is_empty() == false {
-        info!("initializing db.");
-        let conn = database::open_db_connection(&CONFIG.database).expect("failed to open database");
-        database::create_statistics_table(&conn).expect("failed to create statistics table");
-
-        let mut s = stats.lock().unwrap();
-        let mut d = dash.lock().unwrap();
-
-        // Reading the database
-        s.date     = database::read_row(&conn, "date").unwrap();
-        s.hashrate = database::read_row(&conn, "hashrate").unwrap();
-        s.txns     = database::read_row(&conn, "txns").unwrap();
-        s.fees     = database::read_row(&conn, "fees").unwrap();
-        s.utxos    = database::read_row(&conn, "utxos").unwrap();
-        s.kernels  = database::read_row(&conn, "kernels").unwrap();
-
-        // Read utxos right here, because we have it in worker::stats thread launched next day only
-        if s.utxos.is_empty() == false {
-            d.utxo_count = s.utxos.get(s.utxos.len() - 1).unwrap().to_string();
-        }
 
-        // Get the latest date
-        if s.date.is_empty() == false {
-            date = s.date.get(s.date.len() - 1).unwrap().to_string();
+    let networks       = Networks::load();
+    let api_keys       = Arc::new(Mutex::new(apikeys::load()));
+    let api_keys_clone = api_keys.clone();
+
+    // Networks list for the network switcher and display currencies of each network
+    let switcher: Vec<(String, String)> = networks.0.iter().map(|n| (n.config.name.clone(), n.config.url.clone())).collect();
+    let currencies: HashMap<String, Vec<String>> = networks.0.iter().map(|n| (n.config.name.clone(), n.config.currencies.clone()))
+                                                           .collect();
+
+    for net in networks.0.iter() {
+        let net = net.clone();
+
+        let mut ready_data  = false;
+        let mut ready_stats = false;
+        let mut ready_db    = false;
+        let mut date        = "".to_string();
+
+        // Initializing db and table
+        if net.config.database.is_empty() == false {
+            info!("initializing db ({}).", net.config.name);
+            let conn = database::open_db_connection(&net.config.database).expect("failed to open database");
+            database::create_statistics_table(&conn).expect("failed to create statistics table");
+            database::create_prices_table(&conn).expect("failed to create prices table");
+            database::create_peers_table(&conn).expect("failed to create peers table");
+            database::create_versions_table(&conn).expect("failed to create versions table");
+            database::create_pubnode_probes_table(&conn).expect("failed to create public nodes table");
+            database::create_index_tables(&conn).expect("failed to create index tables");
+            database::create_transactions_table(&conn).expect("failed to create transactions table");
+
+            let mut s = net.stats.lock().unwrap();
+            let mut d = net.dash.lock().unwrap();
+
+            // Reading the database
+            s.date     = database::read_row(&conn, "date").unwrap();
+            s.hashrate = database::read_row(&conn, "hashrate").unwrap();
+            s.txns     = database::read_row(&conn, "txns").unwrap();
+            s.fees     = database::read_row(&conn, "fees").unwrap();
+            s.utxos    = database::read_row(&conn, "utxos").unwrap();
+            s.kernels  = database::read_row(&conn, "kernels").unwrap();
+            s.versions = database::read_versions(&conn).unwrap();
+
+            // Read utxos right here, because we have it in worker::stats thread launched next day only
+            if s.utxos.is_empty() == false {
+                d.utxo_count = s.utxos.get(s.utxos.len() - 1).unwrap().to_string();
+            }
+
+            // Get the latest date
+            if s.date.is_empty() == false {
+                date = s.date.get(s.date.len() - 1).unwrap().to_string();
+            }
+
+            drop(s);
+            prices::load_history(&net, &conn).expect("failed to read prices");
+            crawler::load(&net, &conn).expect("failed to read peers");
+            net.netstats.lock().unwrap().pub_days = database::read_pubnode_days(&conn, &requests::pubnode_since())
+                                                    .expect("failed to read public nodes history");
+            crawler::load_history(&net, &conn).expect("failed to read network size");
         }
-    }
 
-    // Collecting main data
-    tokio::spawn(async move {
-        loop {
-            let result = worker::data(dash_clone.clone(), blocks_clone.clone(),
-                                      txns_clone.clone(), stats_clone.clone(),
-                                      netstats_clone.clone()).await;
-            
-            match result {
-                Ok(_v)  => {
-                    if ready_data == false {
-                        ready_data = true;
-                        info!("worker::data ready.");
+        // Indexing the chain
+        if indexer::enabled(&net) {
+            let indexer_net = net.clone();
+
+            tokio::spawn(async move {
+                loop {
+                    let behind = match indexer::update(&indexer_net).await {
+                        Ok(behind) => behind,
+                        Err(e)     => {
+                                          error!("{}", e);
+                                          false
+                                      },
+                    };
+
+                    if !behind {
+                        tokio::time::sleep(Duration::from_secs(15)).await;
                     }
-                },
-                Err(e) => {
-                    ready_data = false;
+                }
+            });
+        }
+
+        // Crawling the network
+        let crawler_net = net.clone();
+
+        tokio::spawn(async move {
+            // Letting the node data load first
+            tokio::time::sleep(Duration::from_secs(15)).await;
+
+            loop {
+                let mut interval = crawler_net.config.crawl_interval.max(60);
+
+                if let Err(e) = crawler::crawl(&crawler_net).await {
                     error!("{}", e);
-                },
+                    interval = 60;
+                }
+
+                tokio::time::sleep(Duration::from_secs(interval)).await;
             }
+        });
 
-            let date_now = format!("\"{}\"", Utc::now().format("%d-%m-%Y"));
+        // Collecting main data
+        tokio::spawn(async move {
+            loop {
+                let result = worker::data(&net).await;
 
-            if date != date_now {
-                date = date_now;
-                let result = worker::stats(dash_clone.clone(), txns_clone.clone(),
-                                           stats_clone.clone(), netstats_clone.clone()).await;
-            
                 match result {
                     Ok(_v)  => {
-                        if ready_stats == false {
-                            ready_stats = true;
-                            ready_db    = true;
-                            info!("worker::stats ready.");
+                        if ready_data == false {
+                            ready_data = true;
+                            info!("worker::data ready ({}).", net.config.name);
                         }
                     },
                     Err(e) => {
-                        ready_stats = false;
+                        ready_data = false;
                         error!("{}", e);
                     },
                 }
-            // Got stats from DB, indicate ready state
-            } else if ready_db == false && CONFIG.database.is_empty() == false {
-                info!("worker::stats ready.");
-                ready_db = true;
+
+                let date_now = format!("\"{}\"", Utc::now().format("%d-%m-%Y"));
+
+                if date != date_now {
+                    date = date_now;
+                    let result = worker::stats(&net).await;
+
+                    match result {
+                        Ok(_v)  => {
+                            if ready_stats == false {
+                                ready_stats = true;
+                                ready_db    = true;
+                                info!("worker::stats ready ({}).", net.config.name);
+                            }
+                        },
+                        Err(e) => {
+                            ready_stats = false;
+                            error!("{}", e);
+                        },
+                    }
+                // Got stats from DB, indicate ready state
+                } else if ready_db == false && net.config.database.is_empty() == false {
+                    info!("worker::stats ready ({}).", net.config.name);
+                    ready_db = true;
+                }
+
+                tokio::time::sleep(Duration::from_secs(15)).await;
+            }
+        });
+    }
+
+    // Saving API keys usage
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = worker::api_keys(api_keys_clone.clone()) {
+                error!("{}", e);
             }
 
             tokio::time::sleep(Duration::from_secs(15)).await;
         }
     });
-    
+
     // Starting Rocket engine.
     let _ = rocket::build()
-            .manage(dash)
-            .manage(blocks)
-            .manage(txns)
-            .manage(stats)
-            .manage(netstats)
-            .mount("/", routes![index, peers_inbound, peers_outbound, sync_status, market_supply,
+            .manage(networks)
+            .manage(api_keys)
+            .mount("/", routes![index, search_suggest, peers_inbound, peers_outbound, sync_status, market_supply,
                                 inflation_rate, volume_usd, volume_btc, price_usd, price_btc,
-                                mcap_usd, mcap_btc,latest_height, disk_usage, network_hashrate,
+                                mcap_usd, mcap_btc, price_source, latest_height, disk_usage, network_hashrate,
                                 network_difficulty, mempool_txns, mempool_stem, txns_count_1h,
                                 txns_count_24h, block_list, block_link, block_link_color,
                                 block_time, block_txns, block_inputs, block_outputs, block_fees,
@@ -1006,10 +1745,49 @@ async fn main() {
                                 soft_supply, production_cost, reward_ratio, breakeven_cost,
                                 last_block_age, block_list_by_height, block_list_index, search, kernel,
                                 output, api_owner, api_foreign, stats, unspent_outputs, kernels,
-                                emission, api_overview, donate, supply_raw, network])
+                                emission, api_supply, supply_total, supply_circulating, supply_soft,
+                                supply_json, api_versions, api_overview, donate, supply_raw, network, admin_api_keys, admin_login,
+                                upstream_active, block_at, blocks_between, verify_kernel, verify_block, payment_proof_form, payment_proof, verify_payment_proof, broadcast_form, broadcast, transaction, output_proof, coinbase])
             .mount("/static", FileServer::from("static"))
-            .attach(Template::custom(|engines| {engines.tera.register_filter("separate_with_commas", separate_with_commas)}))
+            .attach(Template::custom(move |engines| {
+                engines.tera.register_filter("separate_with_commas", separate_with_commas);
+                engines.tera.register_function("networks", make_networks(switcher.clone()));
+                engines.tera.register_function("currencies", make_currencies(currencies.clone()));
+            }))
             .launch()
             .await;
 }
 
+
+// Tera function returning the list of display currencies of a network, used by the currency switcher.
+fn make_currencies(lists: HashMap<String, Vec<String>>) -> impl tera::Function {
+    move |args: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
+        let network = args.get("network").and_then(|n| n.as_str()).unwrap_or_default();
+
+        Ok(tera::to_value(lists.get(network).cloned().unwrap_or_default()).unwrap())
+    }
+}
+
+
+// Tera function returning the list of networks, used by the network switcher.
+fn make_networks(list: Vec<(String, String)>) -> impl tera::Function {
+    move |_args: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
+        Ok(tera::to_value(&list).unwrap())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commitment_prefix() {
+        assert!(is_commitment_prefix("0812345"));
+        assert!(is_commitment_prefix("09"));
+        assert!(is_commitment_prefix("08a1f0"));
+        assert!(!is_commitment_prefix("812345"));
+        assert!(!is_commitment_prefix("1234"));
+        assert!(!is_commitment_prefix("0"));
+    }
+}
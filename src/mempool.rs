@@ -0,0 +1,156 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::sync::atomic::Ordering;
+
+use crate::data::{KernelFeatures, Transaction, INPUT_WEIGHT, KERNEL_WEIGHT, OUTPUT_WEIGHT};
+use crate::database;
+use crate::networks::Network;
+use crate::requests;
+
+
+// Transactions that were not confirmed for this long are forgotten, in seconds.
+const EXPIRE_AFTER: i64 = 24 * 3600;
+
+
+// Parsing get_unconfirmed_transactions pool entry.
+pub fn parse_entry(entry: &Value, now: i64, height: u64) -> Option<Transaction> {
+    let body = &entry["tx"]["body"];
+
+    let kernels: Vec<(String, String, u64)> = body["kernels"].as_array()?.iter()
+        .map(|k| {
+            let features = KernelFeatures::from_json(&k["features"]);
+            (k["excess"].as_str().unwrap_or_default().to_string(),
+             features.as_ref().map(|f| f.name()).unwrap_or_default().to_string(),
+             features.map(|f| f.fee()).unwrap_or_default())
+        })
+        .collect();
+
+    // Inputs are either commitments or objects with features and commitment
+    let inputs: Vec<String> = body["inputs"].as_array()?.iter()
+        .filter_map(|i| i.as_str().or(i["commit"].as_str()).map(|c| c.to_string()))
+        .collect();
+
+    let outputs: Vec<String> = body["outputs"].as_array()?.iter()
+        .filter_map(|o| o["commit"].as_str().map(|c| c.to_string()))
+        .collect();
+
+    if kernels.is_empty() {
+        return None;
+    }
+
+    let seen = entry["tx_at"].as_str()
+               .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+               .map(|t| t.timestamp())
+               .unwrap_or(now);
+
+    Some(Transaction {
+        id:          0,
+        fee:         kernels.iter().map(|k| k.2).sum(),
+        weight:      kernels.len() as f64 * KERNEL_WEIGHT + inputs.len() as f64 * INPUT_WEIGHT
+                     + outputs.len() as f64 * OUTPUT_WEIGHT,
+        kernels,
+        inputs,
+        outputs,
+        seen,
+        seen_height: height,
+        height:      0,
+        confirmed:   0,
+    })
+}
+
+
+// Kernel excesses and time of a block.
+async fn block_kernels(net: &Network, height: u64) -> Result<(Vec<String>, i64), anyhow::Error> {
+    let params = &format!("[{}, null, null]", height)[..];
+    let resp   = requests::call(net, "get_block", params, "1", "foreign").await?;
+    let block  = &resp["result"]["Ok"];
+    let time   = match block["header"]["timestamp"].as_str() {
+        Some(time) => DateTime::parse_from_rfc3339(time)?.timestamp(),
+        None       => bail!("block {} not found", height),
+    };
+
+    let kernels = block["kernels"].as_array().unwrap_or(&Vec::new()).iter()
+                  .filter_map(|k| k["excess"].as_str().map(|e| e.to_string()))
+                  .collect();
+
+    Ok((kernels, time))
+}
+
+
+// Recording new mempool transactions and confirming the ones that left the mempool.
+pub async fn record(net: &Network) -> Result<(), anyhow::Error> {
+    if net.config.database.is_empty() {
+        return Ok(());
+    }
+
+    let height = match net.dash.lock().unwrap().height.parse::<u64>() {
+        Ok(height) => height,
+        Err(_)     => return Ok(()),
+    };
+
+    let resp = requests::call(net, "get_unconfirmed_transactions", "[]", "1", "foreign").await?;
+    let now  = Utc::now().timestamp();
+    let pool: Vec<Transaction> = resp["result"]["Ok"].as_array().unwrap_or(&Vec::new()).iter()
+                                     .filter_map(|entry| parse_entry(entry, now, height))
+                                     .collect();
+
+    let conn = database::open_db_connection(&net.config.database)?;
+
+    for tx in pool.iter() {
+        let mut known = false;
+
+        for (excess, _, _) in tx.kernels.iter() {
+            known |= database::read_transaction(&conn, excess)?.is_some();
+        }
+
+        if !known {
+            database::save_transaction(&conn, tx)?;
+        }
+    }
+
+    // Pending transactions are searched in the kernels of each new block once,
+    // starting from the oldest one seen when nothing was checked yet.
+    let mut pending = database::read_pending_transactions(&conn)?;
+    let checked     = net.tx_checked.load(Ordering::Relaxed);
+    let from        = pending.iter().map(|tx| tx.seen_height).min().unwrap_or(height + 1).max(checked + 1);
+
+    for block in from..=height {
+        if !pending.is_empty() {
+            let (kernels, time) = block_kernels(net, block).await?;
+
+            for tx in pending.iter().filter(|tx| tx.kernels.iter().any(|k| kernels.contains(&k.0))) {
+                database::confirm_transaction(&conn, tx.id, block, time)?;
+            }
+
+            pending.retain(|tx| !tx.kernels.iter().any(|k| kernels.contains(&k.0)));
+        }
+
+        net.tx_checked.store(block, Ordering::Relaxed);
+    }
+
+    for tx in pending {
+        let excess = &tx.kernels[0].0;
+
+        if pool.iter().any(|p| p.kernels.iter().any(|k| k.0 == *excess)) {
+            continue;
+        }
+
+        if now - tx.seen > EXPIRE_AFTER {
+            database::delete_transaction(&conn, tx.id)?;
+        }
+    }
+
+    Ok(())
+}
+
+
+// Recorded transaction containing the kernel.
+pub fn transaction(net: &Network, excess: &str) -> Option<Transaction> {
+    if net.config.database.is_empty() {
+        return None;
+    }
+
+    let conn = database::open_db_connection(&net.config.database).ok()?;
+
+    database::read_transaction(&conn, excess).ok()?
+}
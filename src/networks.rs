@@ -0,0 +1,110 @@
+use rocket::request::{FromRequest, Outcome, Request};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicI64, AtomicU64, AtomicUsize};
+
+use crate::data::{Block, Dashboard, ExplorerConfig, NetStats, Price, Statistics, Transactions, Upstream};
+use crate::exconfig::{self, CONFIG};
+
+
+// Network served by the explorer: its config, upstream nodes and collected data.
+pub struct Network {
+    pub config:       ExplorerConfig,
+    pub upstreams:    Mutex<Vec<Upstream>>,
+    // Round-robin counter for load balanced calls
+    pub round_robin:  AtomicUsize,
+    // Index of the upstream serving the latest (not load balanced) call
+    pub active:       AtomicUsize,
+    pub dash:         Mutex<Dashboard>,
+    pub blocks:       Mutex<Vec<Block>>,
+    pub txns:         Mutex<Transactions>,
+    pub stats:        Mutex<Statistics>,
+    pub netstats:     Mutex<NetStats>,
+    // Latest sample of each price source and the last poll time
+    pub prices:       Mutex<Vec<Price>>,
+    pub price_polled: AtomicI64,
+    // Public nodes last probe time
+    pub pub_polled:   AtomicI64,
+    // Last block searched for confirmed mempool transactions
+    pub tx_checked:   AtomicU64,
+}
+
+impl Network {
+    pub fn new(config: ExplorerConfig) -> Network {
+        Network {
+            upstreams:    Mutex::new(config.upstreams.clone()),
+            round_robin:  AtomicUsize::new(0),
+            active:       AtomicUsize::new(0),
+            dash:         Mutex::new(Dashboard::new()),
+            blocks:       Mutex::new(Vec::new()),
+            txns:         Mutex::new(Transactions::new()),
+            stats:        Mutex::new(Statistics::new()),
+            netstats:     Mutex::new(NetStats::new()),
+            prices:       Mutex::new(Vec::new()),
+            price_polled: AtomicI64::new(0),
+            pub_polled:   AtomicI64::new(0),
+            tx_checked:   AtomicU64::new(0),
+            config,
+        }
+    }
+
+    // Whether any price source is configured, used to show or hide price data.
+    pub fn price_api(&self) -> String {
+        if self.config.price_sources.is_empty() {
+            "disabled".to_string()
+        } else {
+            "enabled".to_string()
+        }
+    }
+
+    // Host part of the network url, used for routing requests.
+    pub fn host(&self) -> &str {
+        let url = &self.config.url;
+
+        url.split_once("://").map(|(_, host)| host).unwrap_or(url)
+    }
+}
+
+
+// All networks served by the explorer, the first one (Explorer.toml) is the default.
+pub struct Networks(pub Vec<Arc<Network>>);
+
+impl Networks {
+    pub fn load() -> Networks {
+        let mut list = vec![Arc::new(Network::new(CONFIG.clone()))];
+
+        for file in CONFIG.networks.iter() {
+            list.push(Arc::new(Network::new(exconfig::load(file))));
+        }
+
+        Networks(list)
+    }
+}
+
+
+// Network selected by the request Host header, falls back to the default network.
+pub struct Net(pub Arc<Network>);
+
+impl Deref for Net {
+    type Target = Network;
+
+    fn deref(&self) -> &Network {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Net {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let networks = req.rocket().state::<Networks>().unwrap();
+        let host     = req.host().map(|h| h.to_string()).unwrap_or_default();
+
+        let net = networks.0.iter()
+                            .find(|n| !n.host().is_empty() && n.host().eq_ignore_ascii_case(&host))
+                            .unwrap_or(&networks.0[0]);
+
+        Outcome::Success(Net(net.clone()))
+    }
+}
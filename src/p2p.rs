@@ -0,0 +1,283 @@
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use rocket::tokio;
+use rocket::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use rocket::tokio::net::TcpStream;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use crate::exconfig::CONFIG;
+
+
+// Grin P2P protocol.
+// https://github.com/mimblewimble/grin/blob/master/p2p/src/msg.rs
+const MAGIC_MAIN:      [u8; 2] = [97, 61];
+const MAGIC_TEST:      [u8; 2] = [83, 59];
+const GENESIS_MAIN:    &str    = "40adad0aec27797b48840aa9e00472015c21baea118ce7a2ff1a82c0f8f5bf82";
+const GENESIS_TEST:    &str    = "edc758c1370d43e1d733f70f58cf187c3be8242830429b1676b89fd91ccf2dab";
+const PROTOCOL:        u32     = 1000;
+const USER_AGENT:      &str    = "MW/grin-explorer";
+const MSG_ERROR:       u8      = 0;
+const MSG_HAND:        u8      = 1;
+const MSG_SHAKE:       u8      = 2;
+const HEADER_LEN:      usize   = 11;
+const MAX_SHAKE_LEN:   u64     = 1024;
+
+
+// Peer data received in Shake message.
+#[derive(Debug, Clone)]
+pub struct Shake {
+    pub version:          u32,
+    pub capabilities:     u32,
+    pub total_difficulty: u64,
+    pub user_agent:       String,
+    pub genesis:          String,
+}
+
+
+fn magic(chain: &str) -> [u8; 2] {
+    if chain == "test" { MAGIC_TEST } else { MAGIC_MAIN }
+}
+
+pub fn genesis(chain: &str) -> &'static str {
+    if chain == "test" { GENESIS_TEST } else { GENESIS_MAIN }
+}
+
+
+fn write_addr(buf: &mut Vec<u8>, addr: SocketAddr) {
+    match addr.ip() {
+        IpAddr::V4(ip) => {
+                              buf.push(0);
+                              buf.extend_from_slice(&ip.octets());
+                          },
+        IpAddr::V6(ip) => {
+                              buf.push(1);
+                              for segment in ip.segments() {
+                                  buf.extend_from_slice(&segment.to_be_bytes());
+                              }
+                          },
+    }
+
+    buf.extend_from_slice(&addr.port().to_be_bytes());
+}
+
+
+// Building Hand message (header and body), all integers are big-endian.
+fn hand(addr: SocketAddr, chain: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+
+    body.extend_from_slice(&PROTOCOL.to_be_bytes());
+    // No capabilities, we don't serve any data
+    body.extend_from_slice(&0u32.to_be_bytes());
+    // Nonce, used by the peer to detect connections to itself
+    body.extend_from_slice(&(Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64).to_be_bytes());
+    // Total difficulty
+    body.extend_from_slice(&0u64.to_be_bytes());
+    write_addr(&mut body, SocketAddr::from(([0, 0, 0, 0], 0)));
+    write_addr(&mut body, addr);
+    body.extend_from_slice(&(USER_AGENT.len() as u64).to_be_bytes());
+    body.extend_from_slice(USER_AGENT.as_bytes());
+
+    for i in (0..genesis(chain).len()).step_by(2) {
+        body.push(u8::from_str_radix(&genesis(chain)[i..i + 2], 16).unwrap());
+    }
+
+    let mut msg = Vec::new();
+
+    msg.extend_from_slice(&magic(chain));
+    msg.push(MSG_HAND);
+    msg.extend_from_slice(&(body.len() as u64).to_be_bytes());
+    msg.extend_from_slice(&body);
+
+    msg
+}
+
+
+// Parsing Shake message body.
+fn parse_shake(body: &[u8]) -> Result<Shake, anyhow::Error> {
+    let u32_at = |pos: usize| -> Result<u32, anyhow::Error> {
+        Ok(u32::from_be_bytes(body.get(pos..pos + 4).ok_or(anyhow!("shake too short"))?.try_into()?))
+    };
+    let u64_at = |pos: usize| -> Result<u64, anyhow::Error> {
+        Ok(u64::from_be_bytes(body.get(pos..pos + 8).ok_or(anyhow!("shake too short"))?.try_into()?))
+    };
+
+    let ua_end  = 24usize.checked_add(u64_at(16)? as usize).ok_or(anyhow!("bad user agent length"))?;
+    let ua      = body.get(24..ua_end).ok_or(anyhow!("shake too short"))?;
+    let genesis = body.get(ua_end..ua_end.saturating_add(32)).ok_or(anyhow!("shake too short"))?;
+
+    Ok(Shake {
+        version:          u32_at(0)?,
+        capabilities:     u32_at(4)?,
+        total_difficulty: u64_at(8)?,
+        user_agent:       String::from_utf8_lossy(ua).to_string(),
+        genesis:          genesis.iter().map(|b| format!("{:02x}", b)).collect(),
+    })
+}
+
+
+async fn exchange(stream: &mut TcpStream, addr: SocketAddr, chain: &str) -> Result<Shake, anyhow::Error> {
+    stream.write_all(&hand(addr, chain)).await?;
+
+    let mut header = [0u8; HEADER_LEN];
+
+    stream.read_exact(&mut header).await?;
+
+    // Nodes of other networks (or other services) close the connection or answer with another magic
+    if header[0..2] != magic(chain) {
+        bail!("unexpected magic {:?}", &header[0..2]);
+    }
+
+    let len = u64::from_be_bytes(header[3..11].try_into()?);
+
+    match header[2] {
+        MSG_SHAKE if len <= MAX_SHAKE_LEN => {},
+        MSG_ERROR                         => bail!("peer refused handshake"),
+        msg_type                          => bail!("unexpected message type {}, length {}", msg_type, len),
+    }
+
+    let mut body = vec![0u8; len as usize];
+
+    stream.read_exact(&mut body).await?;
+
+    parse_shake(&body)
+}
+
+
+// Performing Hand/Shake exchange with a peer of the given chain ("main" or "test").
+// Fails if the peer can't be connected, returns None if it doesn't complete the handshake.
+pub async fn handshake(addr: SocketAddr, chain: &str) -> Result<Option<Shake>, anyhow::Error> {
+    let timeout = Duration::from_millis(CONFIG.probe_timeout);
+
+    let mut stream = match tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+        Ok(result) => result?,
+        Err(_e)    => bail!("connection timed out"),
+    };
+
+    match tokio::time::timeout(timeout, exchange(&mut stream, addr, chain)).await {
+        Ok(Ok(shake)) => Ok(Some(shake)),
+        Ok(Err(e))    => {
+                             debug!("{} handshake failed: {}", addr, e);
+                             Ok(None)
+                         },
+        Err(_e)       => Ok(None),
+    }
+}
+
+
+// Handshaking a list of addresses concurrently.
+pub async fn handshake_all(addrs: Vec<String>, chain: &str) -> Vec<(String, Result<Option<Shake>, anyhow::Error>)> {
+    stream::iter(addrs)
+        .map(|addr| async move {
+            let result = match addr.parse::<SocketAddr>() {
+                Ok(socket_addr) => handshake(socket_addr, chain).await,
+                Err(e)          => Err(e.into()),
+            };
+
+            (addr, result)
+        })
+        .buffer_unordered(CONFIG.probe_concurrency.max(1))
+        .collect()
+        .await
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::tokio::net::TcpListener;
+
+    fn shake_body(user_agent: &str, genesis: &str) -> Vec<u8> {
+        let mut body = Vec::new();
+
+        body.extend_from_slice(&1000u32.to_be_bytes());
+        body.extend_from_slice(&0x2fu32.to_be_bytes());
+        body.extend_from_slice(&1234567890u64.to_be_bytes());
+        body.extend_from_slice(&(user_agent.len() as u64).to_be_bytes());
+        body.extend_from_slice(user_agent.as_bytes());
+        body.extend_from_slice(&crate::verify::from_hex(genesis).unwrap());
+
+        body
+    }
+
+    // Fake peer answering Hand with the given message, returns our address and the received Hand.
+    async fn peer(magic: [u8; 2], msg_type: u8, body: Vec<u8>) -> (SocketAddr, tokio::task::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr     = listener.local_addr().unwrap();
+
+        let task = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut header      = [0u8; HEADER_LEN];
+
+            stream.read_exact(&mut header).await.unwrap();
+
+            let mut hand = vec![0u8; u64::from_be_bytes(header[3..11].try_into().unwrap()) as usize];
+
+            stream.read_exact(&mut hand).await.unwrap();
+
+            let mut msg = Vec::new();
+
+            msg.extend_from_slice(&magic);
+            msg.push(msg_type);
+            msg.extend_from_slice(&(body.len() as u64).to_be_bytes());
+            msg.extend_from_slice(&body);
+
+            stream.write_all(&msg).await.unwrap();
+
+            [header.to_vec(), hand].concat()
+        });
+
+        (addr, task)
+    }
+
+    #[rocket::async_test]
+    async fn hand_and_shake() {
+        let (addr, task) = peer(MAGIC_MAIN, MSG_SHAKE, shake_body("MW/Grin 5.3.3", GENESIS_MAIN)).await;
+        let mut stream   = TcpStream::connect(addr).await.unwrap();
+
+        let shake = exchange(&mut stream, addr, "main").await.unwrap();
+
+        assert_eq!(shake.version, 1000);
+        assert_eq!(shake.capabilities, 0x2f);
+        assert_eq!(shake.total_difficulty, 1234567890);
+        assert_eq!(shake.user_agent, "MW/Grin 5.3.3");
+        assert_eq!(shake.genesis, genesis("main"));
+
+        let hand = task.await.unwrap();
+
+        assert_eq!(hand[0..3], [MAGIC_MAIN[0], MAGIC_MAIN[1], MSG_HAND]);
+        assert!(hand.ends_with(&crate::verify::from_hex(GENESIS_MAIN).unwrap()));
+    }
+
+    #[rocket::async_test]
+    async fn wrong_magic_and_error() {
+        let (addr, _task) = peer(MAGIC_TEST, MSG_SHAKE, shake_body("MW/Grin 5.3.3", GENESIS_TEST)).await;
+        let mut stream    = TcpStream::connect(addr).await.unwrap();
+
+        let e = exchange(&mut stream, addr, "main").await.unwrap_err();
+
+        assert_eq!(e.to_string(), "unexpected magic [83, 59]");
+
+        let (addr, _task) = peer(MAGIC_MAIN, MSG_ERROR, Vec::new()).await;
+        let mut stream    = TcpStream::connect(addr).await.unwrap();
+
+        let e = exchange(&mut stream, addr, "main").await.unwrap_err();
+
+        assert_eq!(e.to_string(), "peer refused handshake");
+    }
+
+    #[test]
+    fn short_shake() {
+        let body = shake_body("MW/Grin 5.3.3", GENESIS_MAIN);
+
+        assert!(parse_shake(&body).is_ok());
+        assert_eq!(parse_shake(&body[..body.len() - 1]).unwrap_err().to_string(), "shake too short");
+
+        // User agent length close to u64::MAX must not overflow
+        let mut body = body;
+
+        body[16..24].copy_from_slice(&u64::MAX.to_be_bytes());
+
+        assert!(parse_shake(&body).is_err());
+    }
+}
@@ -0,0 +1,510 @@
+use chrono::{NaiveDate, Utc};
+use futures::future::join_all;
+use num_format::{Locale, ToFormattedString};
+use rocket::http::{Cookie, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rusqlite::Connection;
+use serde_json::Value;
+use std::fs;
+use std::sync::atomic::Ordering;
+
+use crate::data::{Dashboard, Price, PriceSource};
+use crate::database;
+use crate::http;
+use crate::networks::{Net, Network};
+
+
+const COINGECKO_URL: &str = "https://api.coingecko.com/api/v3/simple/price?ids=grin&include_24hr_vol=true&vs_currencies=usd%2Cbtc";
+
+
+// Source of GRIN price data.
+#[rocket::async_trait]
+pub trait PriceProvider: Send + Sync {
+    fn name(&self) -> String;
+
+    async fn fetch(&self) -> Result<Price, anyhow::Error>;
+}
+
+
+// CoinGecko simple price API.
+pub struct CoinGecko {
+    pub url:        String,
+    pub currencies: Vec<String>,
+}
+
+#[rocket::async_trait]
+impl PriceProvider for CoinGecko {
+    fn name(&self) -> String {
+        "CoinGecko".to_string()
+    }
+
+    async fn fetch(&self) -> Result<Price, anyhow::Error> {
+        let val: Value = http::send(http::CLIENT.get(&self.url), true).await?.json().await?;
+
+        // Check if CoingGecko API returned error
+        if let Some(status) = val.get("status") {
+            bail!("{}", status["error_message"]);
+        }
+
+        let mut price = Price::new(&self.name());
+
+        price.usd        = val["grin"]["usd"].as_f64();
+        price.btc        = val["grin"]["btc"].as_f64();
+        price.volume_usd = val["grin"]["usd_24h_vol"].as_f64();
+        price.volume_btc = val["grin"]["btc_24h_vol"].as_f64();
+        price.time       = Utc::now().timestamp();
+
+        for code in self.currencies.iter() {
+            if let Some(v) = val["grin"][code].as_f64() {
+                price.fiat.insert(code.clone(), v);
+            }
+        }
+
+        Ok(price)
+    }
+}
+
+
+// Generic exchange ticker, fields are picked by JSON pointers (e.g. "/data/last").
+pub struct Ticker {
+    pub source: PriceSource,
+}
+
+#[rocket::async_trait]
+impl PriceProvider for Ticker {
+    fn name(&self) -> String {
+        self.source.name.clone()
+    }
+
+    async fn fetch(&self) -> Result<Price, anyhow::Error> {
+        let val: Value = http::send(http::CLIENT.get(&self.source.url), true).await?.json().await?;
+        let mut price  = Price::new(&self.name());
+
+        price.usd        = field(&val, &self.source.usd);
+        price.btc        = field(&val, &self.source.btc);
+        price.volume_usd = field(&val, &self.source.volume_usd);
+        price.volume_btc = field(&val, &self.source.volume_btc);
+        price.time       = Utc::now().timestamp();
+
+        if price.usd.is_none() && price.btc.is_none() {
+            bail!("{}: no price in ticker response", self.name());
+        }
+
+        Ok(price)
+    }
+}
+
+
+// Manually maintained JSON file, e.g. {"usd": 0.03, "btc": 0.0000003, "eur": 0.028}.
+// File modification time is used as the price time.
+pub struct StaticFile {
+    pub source:     PriceSource,
+    pub currencies: Vec<String>,
+}
+
+#[rocket::async_trait]
+impl PriceProvider for StaticFile {
+    fn name(&self) -> String {
+        self.source.name.clone()
+    }
+
+    async fn fetch(&self) -> Result<Price, anyhow::Error> {
+        let val: Value = serde_json::from_str(&fs::read_to_string(&self.source.path)?)?;
+        let mut price  = Price::new(&self.name());
+
+        price.usd        = field(&val, "/usd");
+        price.btc        = field(&val, "/btc");
+        price.volume_usd = field(&val, "/volume_usd");
+        price.volume_btc = field(&val, "/volume_btc");
+        // Manually set prices without a time never go stale
+        price.time       = val["time"].as_i64().unwrap_or(Utc::now().timestamp());
+
+        for code in self.currencies.iter() {
+            if let Some(v) = field(&val, &format!("/{}", code)) {
+                price.fiat.insert(code.clone(), v);
+            }
+        }
+
+        Ok(price)
+    }
+}
+
+
+// Reading a number by JSON pointer, exchanges often return numbers as strings.
+fn field(val: &Value, pointer: &str) -> Option<f64> {
+    if pointer.is_empty() {
+        return None;
+    }
+
+    match val.pointer(pointer)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _                => None,
+    }
+}
+
+
+// Building providers from the config, ordered by priority.
+// Currencies are the fiat currencies (besides USD) to request where the source supports it.
+pub fn providers(sources: &[PriceSource], currencies: &[String]) -> Vec<Box<dyn PriceProvider>> {
+    let mut list: Vec<Box<dyn PriceProvider>> = Vec::new();
+    let currencies: Vec<String>               = currencies.iter().filter(|c| *c != "usd").cloned().collect();
+
+    for source in sources {
+        let mut source = source.clone();
+
+        if source.name.is_empty() {
+            source.name = source.kind.clone();
+        }
+
+        match source.kind.as_str() {
+            "coingecko" => {
+                               let mut url = source.url;
+
+                               if url.is_empty() {
+                                   url = COINGECKO_URL.to_string();
+
+                                   for code in currencies.iter() {
+                                       url.push_str(&format!("%2C{}", code));
+                                   }
+                               }
+
+                               list.push(Box::new(CoinGecko { url, currencies: currencies.clone() }));
+                           },
+            "ticker"    => list.push(Box::new(Ticker { source })),
+            "file"      => list.push(Box::new(StaticFile { source, currencies: currencies.clone() })),
+            _           => warn!("unknown price source type: {}", source.kind),
+        }
+    }
+
+    list
+}
+
+
+// Polling price providers once the poll interval has passed, returns whether the providers were polled.
+// The latest sample of each provider is kept, failed providers keep their previous sample.
+pub async fn update(net: &Network) -> bool {
+    let now = Utc::now().timestamp();
+
+    if now - net.price_polled.load(Ordering::Relaxed) < net.config.price_interval {
+        return false;
+    }
+
+    net.price_polled.store(now, Ordering::Relaxed);
+
+    let providers = providers(&net.config.price_sources, &net.config.currencies);
+    let results   = join_all(providers.iter().map(|p| p.fetch())).await;
+
+    let mut prices = net.prices.lock().unwrap();
+
+    for (provider, result) in providers.iter().zip(results) {
+        match result {
+            Ok(price) => {
+                             prices.retain(|p| p.source != price.source);
+                             prices.push(price);
+                         },
+            Err(e)    => warn!("price source {} failed: {}", provider.name(), e),
+        }
+    }
+
+    true
+}
+
+
+// Aggregating fresh samples, stale ones (older than price_stale) are ignored.
+// Returns the aggregated price, or None if there is no fresh price.
+pub fn aggregate(net: &Network) -> Option<Price> {
+    let now    = Utc::now().timestamp();
+    let prices = net.prices.lock().unwrap();
+    let order  = providers(&net.config.price_sources, &net.config.currencies).iter().map(|p| p.name()).collect::<Vec<String>>();
+
+    // Fresh samples in priority order
+    let mut fresh: Vec<&Price> = prices.iter().filter(|p| now - p.time <= net.config.price_stale).collect();
+
+    fresh.sort_by_key(|p| order.iter().position(|name| *name == p.source));
+
+    if fresh.is_empty() {
+        return None;
+    }
+
+    if net.config.price_aggregation != "median" {
+        return Some(fresh[0].clone());
+    }
+
+    let mut price = Price::new(&fresh.iter().map(|p| p.source.clone()).collect::<Vec<String>>().join(", "));
+
+    price.usd        = median(fresh.iter().filter_map(|p| p.usd).collect());
+    price.btc        = median(fresh.iter().filter_map(|p| p.btc).collect());
+    // Volumes are not comparable between sources, take the first source reporting them
+    price.volume_usd = fresh.iter().find_map(|p| p.volume_usd);
+    price.volume_btc = fresh.iter().find_map(|p| p.volume_btc);
+    price.time       = fresh.iter().map(|p| p.time).min().unwrap_or(now);
+
+    for code in net.config.currencies.iter() {
+        if let Some(v) = median(fresh.iter().filter_map(|p| p.fiat.get(code).copied()).collect()) {
+            price.fiat.insert(code.clone(), v);
+        }
+    }
+
+    Some(price)
+}
+
+
+// Median of a list of values.
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_by(|a, b| a.total_cmp(b));
+
+    let mid = values.len() / 2;
+
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+
+// Display currency, chosen by the visitor with ?currency= query param and remembered in a cookie.
+pub struct Currency(pub String);
+
+impl Currency {
+    pub fn symbol(&self) -> String {
+        match self.0.as_str() {
+            "usd" => "$".to_string(),
+            "eur" => "€".to_string(),
+            "gbp" => "£".to_string(),
+            "jpy" => "¥".to_string(),
+            "cny" => "¥".to_string(),
+            "rub" => "₽".to_string(),
+            "krw" => "₩".to_string(),
+            "inr" => "₹".to_string(),
+            "try" => "₺".to_string(),
+            code  => code.to_uppercase(),
+        }
+    }
+
+    // USD to display currency exchange rate, derived from GRIN prices.
+    pub fn rate(&self, data: &Dashboard) -> Option<f64> {
+        if self.0 == "usd" {
+            return Some(1.0);
+        }
+
+        let usd  = data.fiat.get("usd")?;
+        let fiat = data.fiat.get(&self.0)?;
+
+        if *usd > 0.0 {
+            Some(fiat / usd)
+        } else {
+            None
+        }
+    }
+
+    // Converting a USD amount formatted by the collectors into the display currency.
+    pub fn display(&self, data: &Dashboard, usd: &str, decimals: usize) -> String {
+        if self.0 == "usd" || usd.is_empty() {
+            return format!("{} {}", self.symbol(), usd);
+        }
+
+        let value = match (usd.replace(',', "").parse::<f64>(), self.rate(data)) {
+            (Ok(v), Some(rate)) => v * rate,
+            _                   => return format!("{} ", self.symbol()),
+        };
+
+        if decimals == 0 {
+            format!("{} {}", self.symbol(), (value as u64).to_formatted_string(&Locale::en))
+        } else {
+            format!("{} {:.*}", self.symbol(), decimals, value)
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Currency {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        // Currencies of the requested network
+        let net   = match req.guard::<Net>().await {
+                        Outcome::Success(net) => net,
+                        _                     => return Outcome::Forward(Status::InternalServerError),
+                    };
+        let codes = &net.config.currencies;
+
+        if let Some(Ok(code)) = req.query_value::<&str>("currency") {
+            let code = code.to_lowercase();
+
+            if codes.contains(&code) {
+                req.cookies().add(Cookie::build(("currency", code.clone())).path("/").permanent());
+                return Outcome::Success(Currency(code));
+            }
+        }
+
+        if let Some(cookie) = req.cookies().get("currency") {
+            if codes.iter().any(|c| c == cookie.value()) {
+                return Outcome::Success(Currency(cookie.value().to_string()));
+            }
+        }
+
+        Outcome::Success(Currency(codes.first().cloned().unwrap_or("usd".to_string())))
+    }
+}
+
+
+// Aligning daily prices stored in the database with the statistics dates.
+pub fn load_history(net: &Network, conn: &Connection) -> Result<(), anyhow::Error> {
+    let daily     = database::read_daily_prices(conn)?;
+    let mut stats = net.stats.lock().unwrap();
+
+    let mut price = Vec::new();
+    let mut mcap  = Vec::new();
+
+    for date in stats.date.iter() {
+        // Statistics dates are stored as "DD-MM-YYYY" strings
+        let date = NaiveDate::parse_from_str(date.trim_matches('"'), "%d-%m-%Y")
+                   .map(|d| d.format("%Y-%m-%d").to_string())
+                   .unwrap_or_default();
+
+        match daily.iter().find(|d| d.0 == date) {
+            Some((_, usd, cap)) => {
+                                       price.push(usd.map(|v| format!("{:.4}", v)).unwrap_or("null".to_string()));
+                                       mcap.push(cap.map(|v| format!("{:.0}", v)).unwrap_or("null".to_string()));
+                                   },
+            None                => {
+                                       price.push("null".to_string());
+                                       mcap.push("null".to_string());
+                                   },
+        }
+    }
+
+    stats.price = price;
+    stats.mcap  = mcap;
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::ExplorerConfig;
+
+    fn network(aggregation: &str) -> Network {
+        let mut config = ExplorerConfig::new();
+
+        for name in ["first", "second", "third"] {
+            let mut source = PriceSource::new();
+
+            source.kind = "ticker".to_string();
+            source.name = name.to_string();
+            config.price_sources.push(source);
+        }
+
+        config.price_aggregation = aggregation.to_string();
+        config.currencies        = vec!["usd".to_string(), "eur".to_string()];
+
+        Network::new(config)
+    }
+
+    fn price(source: &str, usd: f64, age: i64) -> Price {
+        let mut price = Price::new(source);
+
+        price.usd  = Some(usd);
+        price.time = Utc::now().timestamp() - age;
+        price.fiat.insert("eur".to_string(), usd * 0.9);
+
+        price
+    }
+
+    #[test]
+    fn median_values() {
+        assert_eq!(median(Vec::new()), None);
+        assert_eq!(median(vec![3.0]), Some(3.0));
+        assert_eq!(median(vec![5.0, 1.0, 3.0]), Some(3.0));
+        assert_eq!(median(vec![4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    fn currency_rates() {
+        let mut data = Dashboard::new();
+
+        assert_eq!(Currency("usd".to_string()).rate(&data), Some(1.0));
+        assert_eq!(Currency("eur".to_string()).rate(&data), None);
+
+        data.fiat.insert("usd".to_string(), 0.05);
+        data.fiat.insert("eur".to_string(), 0.045);
+
+        assert_eq!(Currency("eur".to_string()).rate(&data).map(|r| format!("{:.2}", r)), Some("0.90".to_string()));
+    }
+
+    #[test]
+    fn field_pointers() {
+        let val = serde_json::json!({"price": "0.0000012", "volume": 15.5, "name": "GRIN"});
+
+        assert_eq!(field(&val, "/price"), Some(0.0000012));
+        assert_eq!(field(&val, "/volume"), Some(15.5));
+        assert_eq!(field(&val, "/name"), None);
+        assert_eq!(field(&val, "/missing"), None);
+        assert_eq!(field(&val, ""), None);
+    }
+
+    #[rocket::async_test]
+    async fn static_file_time() {
+        let path     = std::env::temp_dir().join(format!("grin-explorer-price-{}.json", std::process::id()));
+        let mut file = StaticFile { source: PriceSource::new(), currencies: vec!["eur".to_string()] };
+
+        file.source.path = path.to_string_lossy().to_string();
+
+        fs::write(&path, r#"{"usd": 0.05, "eur": "0.045", "time": 1700000000}"#).unwrap();
+
+        let price = file.fetch().await.unwrap();
+
+        assert_eq!((price.usd, price.fiat.get("eur").copied(), price.time), (Some(0.05), Some(0.045), 1700000000));
+
+        // Without time the price is current
+        fs::write(&path, r#"{"usd": 0.05}"#).unwrap();
+
+        assert!(Utc::now().timestamp() - file.fetch().await.unwrap().time < 5);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn aggregate_median() {
+        let net = network("median");
+
+        *net.prices.lock().unwrap() = vec![price("third", 0.05, 10), price("first", 0.03, 20),
+                                           price("second", 0.04, 30)];
+
+        let price = aggregate(&net).unwrap();
+
+        assert_eq!(price.usd, Some(0.04));
+        assert_eq!(price.fiat.get("eur").copied(), Some(0.04 * 0.9));
+        assert_eq!(price.source, "first, second, third");
+    }
+
+    #[test]
+    fn aggregate_priority_skips_stale() {
+        let net = network("priority");
+
+        *net.prices.lock().unwrap() = vec![price("first", 0.03, 1000), price("second", 0.04, 10),
+                                           price("third", 0.05, 10)];
+
+        let price = aggregate(&net).unwrap();
+
+        assert_eq!(price.source, "second");
+        assert_eq!(price.usd, Some(0.04));
+    }
+
+    #[test]
+    fn aggregate_all_stale() {
+        let net = network("median");
+
+        *net.prices.lock().unwrap() = vec![price("first", 0.03, 1000)];
+
+        assert!(aggregate(&net).is_none());
+    }
+}
@@ -1,46 +1,74 @@
-use chrono::{Utc, DateTime};
-use country_emoji::code_to_flag;
+use chrono::{Utc, DateTime, TimeDelta};
 use fs_extra::dir::get_size;
+use futures::future::join_all;
 use humantime::format_duration;
 use num_format::{Locale, ToFormattedString};
 use reqwest::Error;
 use serde_json::Value;
-use std::net::{TcpStream, SocketAddr};
-use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::time::Duration;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use std::cmp::Reverse;
 use std::collections::HashMap;
 
-use crate::data::{Block, ConnectedNode, Dashboard, Kernel, NetStats, Output, PublicNode, Statistics, Transactions};
-use crate::data::{KERNEL_WEIGHT, INPUT_WEIGHT, OUTPUT_WEIGHT, KERNEL_SIZE, INPUT_SIZE, OUTPUT_SIZE};
-use crate::exconfig::CONFIG;
+use crate::data::{Block, ConnectedNode, Kernel, KernelFeatures, Output, Price, PubNodeDay, PublicNode, Upstream};
+use crate::data::{COINBASE_MATURITY, KERNEL_WEIGHT, INPUT_WEIGHT, OUTPUT_WEIGHT, KERNEL_SIZE, INPUT_SIZE, OUTPUT_SIZE};
+use crate::database;
+use crate::http;
+use crate::indexer;
+use crate::networks::Network;
+use crate::prices;
+use crate::schedule;
+use crate::upstream;
+use crate::verify;
 
 
 // RPC requests to grin node.
-pub async fn call(method: &str, params: &str, id: &str, rpc_type: &str) -> Result<Value, anyhow::Error> {
-    let rpc_url;
-    let secret;
-
-    if CONFIG.port.is_empty() == false {
-        rpc_url = format!("{}://{}:{}/v2/{}", CONFIG.proto, CONFIG.host, CONFIG.port, rpc_type);
-    } else {
-        rpc_url = format!("{}://{}/v2/{}", CONFIG.proto, CONFIG.host, rpc_type);
+// Upstream nodes are tried in turn until one of them responds.
+pub async fn call(net: &Network, method: &str, params: &str, id: &str, rpc_type: &str) -> Result<Value, anyhow::Error> {
+    let mut last_err = anyhow!("no upstream nodes");
+
+    for (index, node) in upstream::candidates(net, method, rpc_type) {
+        match call_upstream(&node, method, params, id, rpc_type).await {
+            Ok(val) => {
+                           if !upstream::is_balanced(net, method, rpc_type) {
+                               upstream::set_active(net, index);
+                           }
+
+                           return Ok(val);
+                       },
+            Err(e)  => {
+                           warn!("upstream {} failed: {}", node.name, e);
+
+                           // Slow calls are not the node's fault, the health check decides on the rest
+                           if is_connect_error(&e) {
+                               upstream::set_offline(net, index);
+                           }
+
+                           last_err = e;
+                       },
+        }
     }
 
-    if rpc_type == "owner" {
-        secret = CONFIG.api_secret.clone();
-    } else {
-        secret = CONFIG.foreign_api_secret.clone();
-    }
+    Err(last_err)
+}
 
-    let client = reqwest::Client::new();
-    let result = client.post(rpc_url)
-                       .timeout(Duration::from_secs(10))
-                       .body(format!("{{\"method\": \"{}\", \"params\": {}, \"id\": {}, \"jsonrpc\": \"2.0\"}}", method, params, id))
-                       .basic_auth(CONFIG.user.clone(), Some(secret))
-                       .header("content-type", "application/json")
-                       .send()
-                       .await?;
+
+// Node can't be connected at all.
+fn is_connect_error(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<Error>().is_some_and(|e| e.is_connect())
+}
+
+
+// RPC requests to a specified upstream node.
+pub async fn call_upstream(node: &Upstream, method: &str, params: &str, id: &str, rpc_type: &str) -> Result<Value, anyhow::Error> {
+    let rpc_url = format!("{}/v2/{}", node.url, rpc_type);
+    let secret  = if rpc_type == "owner" { node.api_secret.clone() } else { node.foreign_api_secret.clone() };
+
+    let request = http::CLIENT.post(rpc_url)
+                              .body(format!("{{\"method\": \"{}\", \"params\": {}, \"id\": {}, \"jsonrpc\": \"2.0\"}}", method, params, id))
+                              .basic_auth(node.user.clone(), Some(secret))
+                              .header("content-type", "application/json");
+    let result  = http::send(request, method.starts_with("get_")).await?;
 
     match result.error_for_status_ref() {
         Ok(_res) => (),
@@ -53,6 +81,28 @@ pub async fn call(method: &str, params: &str, id: &str, rpc_type: &str) -> Resul
 }
 
 
+// Checking upstream nodes health.
+pub async fn check_upstreams(net: &Network) -> Result<(), anyhow::Error> {
+    let nodes = upstream::list(net);
+
+    let results = join_all(nodes.iter().map(|node| async move {
+        let start = Instant::now();
+
+        match call_upstream(node, "get_tip", "[]", "1", "foreign").await {
+            Ok(resp) => resp["result"]["Ok"]["height"].as_u64().map(|height| (height, start.elapsed().as_millis() as u64)),
+            Err(e)   => {
+                            warn!("upstream {} health check failed: {}", node.name, e);
+                            None
+                        },
+        }
+    })).await;
+
+    upstream::update(net, results);
+
+    Ok(())
+}
+
+
 // RPC requests to grin node.
 // The same call as above but with no api secrets usage and the option to specify custom endpoint.
 pub async fn call_external(method: &str, params: &str, id: &str, rpc_type: &str, endpoint: String) -> Result<Value, anyhow::Error> {
@@ -60,13 +110,10 @@ pub async fn call_external(method: &str, params: &str, id: &str, rpc_type: &str,
 
     rpc_url = format!("{}/v2/{}", endpoint, rpc_type);
 
-    let client = reqwest::Client::new();
-    let result = client.post(rpc_url)
-                       .timeout(Duration::from_secs(10))
-                       .body(format!("{{\"method\": \"{}\", \"params\": {}, \"id\": {}, \"jsonrpc\": \"2.0\"}}", method, params, id))
-                       .header("content-type", "application/json")
-                       .send()
-                       .await?;
+    let request = http::CLIENT.post(rpc_url)
+                              .body(format!("{{\"method\": \"{}\", \"params\": {}, \"id\": {}, \"jsonrpc\": \"2.0\"}}", method, params, id))
+                              .header("content-type", "application/json");
+    let result  = http::send(request, method.starts_with("get_")).await?;
 
     match result.error_for_status_ref() {
         Ok(_res) => (),
@@ -79,20 +126,24 @@ pub async fn call_external(method: &str, params: &str, id: &str, rpc_type: &str,
 }
 
 
-// Collecting: height, sync, node_ver, proto_ver, kernel_mmr_size.
-pub async fn get_status(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), anyhow::Error> {
-    let resp1 = call("get_status", "[]", "1", "owner").await?;
+// Collecting: height, sync, node_ver, proto_ver, total_diff, tip_hash, tip_time, kernel_mmr_size.
+pub async fn get_status(net: &Network) -> Result<(), anyhow::Error> {
+    let resp1 = call(net, "get_status", "[]", "1", "owner").await?;
 
     if resp1 != Value::Null {
         let params = &format!("[{}, null, null]", resp1["result"]["Ok"]["tip"]["height"])[..];
-        let resp2  = call("get_block", params, "1", "foreign").await?;
+        let resp2  = call(net, "get_block", params, "1", "foreign").await?;
 
-        let mut data = dashboard.lock().unwrap();
+        let mut data = net.dash.lock().unwrap();
 
         if resp2 != Value::Null {
             if resp2["result"]["Ok"]["header"]["kernel_mmr_size"] != Value::Null {
                 data.kernel_mmr_size = resp2["result"]["Ok"]["header"]["kernel_mmr_size"].to_string();
             }
+
+            if let Some(Ok(time)) = resp2["result"]["Ok"]["header"]["timestamp"].as_str().map(DateTime::parse_from_rfc3339) {
+                data.tip_time = time.timestamp();
+            }
         }
 
         if resp1["result"]["Ok"]["chain"] == Value::Null {
@@ -103,10 +154,12 @@ pub async fn get_status(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), anyhow::
         } else {
             data.chain = resp1["result"]["Ok"]["chain"].as_str().unwrap().to_string();
         }
-        data.height    = resp1["result"]["Ok"]["tip"]["height"].to_string();
-        data.sync      = resp1["result"]["Ok"]["sync_status"].as_str().unwrap().to_string();
-        data.node_ver  = resp1["result"]["Ok"]["user_agent"].as_str().unwrap().to_string();
-        data.proto_ver = resp1["result"]["Ok"]["protocol_version"].to_string();
+        data.height     = resp1["result"]["Ok"]["tip"]["height"].to_string();
+        data.sync       = resp1["result"]["Ok"]["sync_status"].as_str().unwrap().to_string();
+        data.node_ver   = resp1["result"]["Ok"]["user_agent"].as_str().unwrap().to_string();
+        data.proto_ver  = resp1["result"]["Ok"]["protocol_version"].to_string();
+        data.total_diff = resp1["result"]["Ok"]["tip"]["total_difficulty"].as_u64().unwrap_or_default();
+        data.tip_hash   = resp1["result"]["Ok"]["tip"]["last_block_pushed"].as_str().unwrap_or_default().to_string();
     }
 
     Ok(())
@@ -114,11 +167,11 @@ pub async fn get_status(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), anyhow::
 
 
 // Collecting: txns, stem.
-pub async fn get_mempool(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), anyhow::Error> {
-    let resp1 = call("get_pool_size", "[]", "1", "foreign").await?;
-    let resp2 = call("get_stempool_size", "[]", "1", "foreign").await?;
+pub async fn get_mempool(net: &Network) -> Result<(), anyhow::Error> {
+    let resp1 = call(net, "get_pool_size", "[]", "1", "foreign").await?;
+    let resp2 = call(net, "get_stempool_size", "[]", "1", "foreign").await?;
     
-    let mut data = dashboard.lock().unwrap();
+    let mut data = net.dash.lock().unwrap();
 
     if resp1 != Value::Null && resp1 != Value::Null {
         data.txns = resp1["result"]["Ok"].to_string();
@@ -130,15 +183,14 @@ pub async fn get_mempool(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), anyhow:
 
 
 // Collecting: inbound, outbound, user_agent.
-pub async fn get_connected_peers(dashboard: Arc<Mutex<Dashboard>>, statistics: Arc<Mutex<Statistics>>,
-                                 netstats: Arc<Mutex<NetStats>>) -> Result<(), anyhow::Error> {
+pub async fn get_connected_peers(net: &Network) -> Result<(), anyhow::Error> {
     let mut peers           = HashMap::new();
     let mut addrs           = Vec::new();
     let mut connected_nodes = Vec::<ConnectedNode>::new();
     let mut inbound         = 0;
     let mut outbound        = 0;
 
-    let resp = call("get_connected_peers", "[]", "1", "owner").await?;
+    let resp = call(net, "get_connected_peers", "[]", "1", "owner").await?;
     
     if resp != Value::Null {
         // Collecting peers from local node
@@ -159,6 +211,7 @@ pub async fn get_connected_peers(dashboard: Arc<Mutex<Dashboard>>, statistics: A
                 node.address = peer["addr"].as_str().unwrap().to_string();
                 node.user_agent = peer["user_agent"].as_str().unwrap().to_string();
                 node.bits = peer["capabilities"]["bits"].to_string().parse::<u32>().unwrap();
+                node.version = peer["version"].as_u64().unwrap_or_default() as u32;
                 // Check for BLOCK_HIST bit (archival mode)
                 // https://github.com/mimblewimble/grin/blob/2ec7b4d5cdba44db20d0007a71396e4bfd381cc5/p2p/src/types.rs#L393
                 if node.bits & (1 << 5) != 0 {
@@ -172,7 +225,7 @@ pub async fn get_connected_peers(dashboard: Arc<Mutex<Dashboard>>, statistics: A
     }
 
     // Collecting peers from external endpoints
-    for endpoint in CONFIG.stats_source.clone() {
+    for endpoint in net.config.stats_source.clone() {
         match call_external("get_connected_peers", "[]", "1", "owner", endpoint).await {
             Ok(resp) => {
                             if resp != Value::Null {
@@ -187,6 +240,7 @@ pub async fn get_connected_peers(dashboard: Arc<Mutex<Dashboard>>, statistics: A
                                             node.address = peer["addr"].as_str().unwrap().to_string();
                                             node.user_agent = peer["user_agent"].as_str().unwrap().to_string();
                                             node.bits = peer["capabilities"]["bits"].to_string().parse::<u32>().unwrap();
+                                            node.version = peer["version"].as_u64().unwrap_or_default() as u32;
                                             if node.bits & (1 << 5) != 0 {
                                                 node.is_archival = true;
                                             }
@@ -205,8 +259,8 @@ pub async fn get_connected_peers(dashboard: Arc<Mutex<Dashboard>>, statistics: A
     let mut peers_vec: Vec<(&String, &u32)> = peers.iter().collect();
     peers_vec.sort_by(|a, b| b.1.cmp(a.1));
 
-    let mut dash  = dashboard.lock().unwrap();
-    let mut stats = statistics.lock().unwrap();
+    let mut dash  = net.dash.lock().unwrap();
+    let mut stats = net.stats.lock().unwrap();
 
     stats.user_agent.clear();
     stats.count.clear();
@@ -221,7 +275,7 @@ pub async fn get_connected_peers(dashboard: Arc<Mutex<Dashboard>>, statistics: A
     dash.inbound  = inbound;
     dash.outbound = outbound;
 
-    let mut nstats = netstats.lock().unwrap();
+    let mut nstats = net.netstats.lock().unwrap();
 
     nstats.conn_nodes.clear();
     nstats.conn_nodes = connected_nodes.clone();
@@ -231,56 +285,48 @@ pub async fn get_connected_peers(dashboard: Arc<Mutex<Dashboard>>, statistics: A
 
 
 // Collecting: supply, inflation, price_usd, price_btc, volume_usd, volume_btc, cap_usd, cap_btc.
-pub async fn get_market(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), anyhow::Error> {
-    let client;
-    let result;
-    let mut val = Value::Null;
-
-    static COINGECKO_COUNT: AtomicU32 = AtomicU32::new(0);
-
-    let count = COINGECKO_COUNT.fetch_add(1, Ordering::Relaxed);
-
-    // Call CG API only once every 20 calls (15sec * 20)
-    if CONFIG.coingecko_api == "enabled" && count % 20 == 0 {
-        client = reqwest::Client::new();
-        result = client.get("https://api.coingecko.com/api/v3/simple/price?ids=grin&vs_currencies=usd%2Cbtc&include_24hr_vol=true")
-                       .header("User-Agent", "https://github.com/aglkm/grin-explorer")
-                       .send().await?;
-        val    = serde_json::from_str(&result.text().await?)?;
-    }
-
-    let mut data = dashboard.lock().unwrap();
+pub async fn get_market(net: &Network) -> Result<(), anyhow::Error> {
+    let polled   = prices::update(net).await;
+    let price    = prices::aggregate(net);
+    let mut data = net.dash.lock().unwrap();
   
     if data.height.is_empty() == false {
-        // Calculating coin supply
-        // Adding +1 as block index starts with 0
-        let supply = (data.height.parse::<u64>().unwrap() + 1) * 60;
-
-        // 31536000 seconds in a year
-        let inflation = (31536000.0 / (supply as f64)) * 100.0;
+        let height    = data.height.parse::<u64>().unwrap();
+        let supply    = schedule::supply(height);
+        let inflation = schedule::inflation(height);
 
         data.inflation  = format!("{:.2}", inflation);
         data.supply_raw = supply.to_string();
         data.supply     = supply.to_formatted_string(&Locale::en);
 
-        // https://john-tromp.medium.com/a-case-for-using-soft-total-supply-1169a188d153
-        data.soft_supply = format!("{:.2}", supply.to_string().parse::<f64>().unwrap() / 3150000000.0 * 100.0);
+        data.soft_supply = format!("{:.2}", supply as f64 / schedule::SOFT_SUPPLY as f64 * 100.0);
     
-        if CONFIG.coingecko_api == "enabled" && val != Value::Null {
-            // Check if CoingGecko API returned error
-            if let Some(status) = val.get("status") {
-                warn!("{}", status["error_message"].to_string());
-            } else {
-                data.price_usd  = format!("{:.3}", val["grin"]["usd"].to_string().parse::<f64>().unwrap());
-                data.price_btc  = format!("{:.8}", val["grin"]["btc"].to_string().parse::<f64>().unwrap());
-                data.volume_usd = (val["grin"]["usd_24h_vol"].to_string().parse::<f64>().unwrap() as u64)
-                                  .to_formatted_string(&Locale::en);
-                data.volume_btc = format!("{:.2}", val["grin"]["btc_24h_vol"].to_string().parse::<f64>().unwrap());
-                data.cap_usd    = (((supply as f64) * data.price_usd.parse::<f64>().unwrap()) as u64)
-                                  .to_formatted_string(&Locale::en);
-                data.cap_btc    = (((supply as f64) * data.price_btc.parse::<f64>().unwrap()) as u64)
-                                  .to_formatted_string(&Locale::en);
-            }
+        // Stale or missing prices are hidden
+        let price = price.unwrap_or_else(|| Price::new(""));
+
+        data.price_usd    = price.usd.map(|v| format!("{:.3}", v)).unwrap_or_default();
+        data.price_btc    = price.btc.map(|v| format!("{:.8}", v)).unwrap_or_default();
+        data.volume_usd   = price.volume_usd.map(|v| (v as u64).to_formatted_string(&Locale::en)).unwrap_or_default();
+        data.volume_btc   = price.volume_btc.map(|v| format!("{:.2}", v)).unwrap_or_default();
+        data.cap_usd      = price.usd.map(|v| (((supply as f64) * v) as u64).to_formatted_string(&Locale::en)).unwrap_or_default();
+        data.cap_btc      = price.btc.map(|v| (((supply as f64) * v) as u64).to_formatted_string(&Locale::en)).unwrap_or_default();
+        data.price_source = price.source.clone();
+        data.fiat         = price.fiat.clone();
+
+        if let Some(usd) = price.usd {
+            data.fiat.insert("usd".to_string(), usd);
+        }
+
+        // Storing a new price sample
+        if polled && !price.source.is_empty() && !net.config.database.is_empty() {
+            let cap_usd = price.usd.map(|v| (supply as f64) * v);
+
+            drop(data);
+
+            let conn = database::open_db_connection(&net.config.database)?;
+
+            database::save_price(&conn, &price, cap_usd)?;
+            prices::load_history(net, &conn)?;
         }
     }
 
@@ -289,24 +335,24 @@ pub async fn get_market(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), anyhow::
 
 
 // Collecting: disk_usage.
-pub fn get_disk_usage(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), Error> { 
-    let mut data = dashboard.lock().unwrap();
+pub fn get_disk_usage(net: &Network) -> Result<(), Error> { 
+    let mut data = net.dash.lock().unwrap();
     let chain_dir;
 
     if data.chain == "main" {
-        chain_dir = format!("{}/main/chain_data", CONFIG.grin_dir);
+        chain_dir = format!("{}/main/chain_data", net.config.grin_dir);
     } else if data.chain == "test" {
-        chain_dir = format!("{}/test/chain_data", CONFIG.grin_dir);
+        chain_dir = format!("{}/test/chain_data", net.config.grin_dir);
     } else {
         // Chain parameter in get_status() rpc is added in 5.3.3 node.
         // Default to main chain in case of node version less than 5.3.3.
-        chain_dir = format!("{}/main/chain_data", CONFIG.grin_dir);
+        chain_dir = format!("{}/main/chain_data", net.config.grin_dir);
     }
 
     match get_size(chain_dir.clone()) {
         Ok(chain_size) => data.disk_usage = format!("{:.2}", (chain_size as f64) / 1000.0 / 1000.0 / 1000.0),
         Err(e)         => {
-            if CONFIG.host == "127.0.0.1" || CONFIG.host == "0.0.0.0" {
+            if net.config.host == "127.0.0.1" || net.config.host == "0.0.0.0" {
                 error!("{}: \"{}\"", e, chain_dir);
             } else {
                 // Ignore error for external node connection
@@ -319,17 +365,17 @@ pub fn get_disk_usage(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), Error> {
 
 
 // Collecting: hashrate, difficulty, production cost, breakeven cost.
-pub async fn get_mining_stats(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), anyhow::Error> {
+pub async fn get_mining_stats(net: &Network) -> Result<(), anyhow::Error> {
     let difficulty_window = 1440;
-    let height            = get_current_height(dashboard.clone());
+    let height            = get_current_height(net);
 
     if height.is_empty() == false && height.parse::<u64>().unwrap() > 1440 {
         let params1 = &format!("[{}, null, null]", height)[..];
         let params2 = &format!("[{}, null, null]", height.parse::<u64>().unwrap() - difficulty_window)[..];
-        let resp1   = call("get_block", params1, "1", "foreign").await?;
-        let resp2   = call("get_block", params2, "1", "foreign").await?;
+        let resp1   = call(net, "get_block", params1, "1", "foreign").await?;
+        let resp2   = call(net, "get_block", params2, "1", "foreign").await?;
     
-        let mut data = dashboard.lock().unwrap();
+        let mut data = net.dash.lock().unwrap();
 
         if resp1 != Value::Null && resp2 != Value::Null &&
            resp1["result"]["Ok"].is_null() == false &&
@@ -358,7 +404,7 @@ pub async fn get_mining_stats(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), an
 
             data.difficulty = net_diff.to_string();
 
-            if CONFIG.coingecko_api == "enabled" {
+            if net.price_api() == "enabled" {
                 // Calculating G1-mini production per hour
                 let coins_per_hour = 1.2 / hashrate * 60.0 * 60.0;
 
@@ -371,6 +417,9 @@ pub async fn get_mining_stats(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), an
                                                         / data.production_cost.parse::<f64>().unwrap());
                     data.breakeven_cost = format!("{:.2}", data.price_usd.parse::<f64>().unwrap()
                                                         / (120.0 / 1000.0 * (1.0 / coins_per_hour)));
+                } else {
+                    data.reward_ratio   = String::new();
+                    data.breakeven_cost = String::new();
                 }
             }
         } else {
@@ -385,11 +434,11 @@ pub async fn get_mining_stats(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), an
 
 
 // Collecting block data for recent blocks (block_list page).
-pub async fn get_block_list_data(height: &String, block: &mut Block)
+pub async fn get_block_list_data(net: &Network, height: &String, block: &mut Block)
                                   -> Result<(), anyhow::Error> {
     if height.is_empty() == false {
         let params = &format!("[{}, null, null]", height)[..];
-        let resp   = call("get_block", params, "1", "foreign").await?;
+        let resp   = call(net, "get_block", params, "1", "foreign").await?;
 
         if resp["result"]["Ok"].is_null() == false {
             block.height = resp["result"]["Ok"]["header"]["height"].to_string();
@@ -448,12 +497,12 @@ pub async fn get_block_list_data(height: &String, block: &mut Block)
 
 
 // Collecting block data.
-pub async fn get_block_data(height: &str, block: &mut Block)
+pub async fn get_block_data(net: &Network, height: &str, block: &mut Block)
              -> Result<(), anyhow::Error> {
     if height.is_empty() == false {
         let params = &format!("[{}, null, null]", height)[..];
 
-        let resp = call("get_block", params, "1", "foreign").await?;
+        let resp = call(net, "get_block", params, "1", "foreign").await?;
 
         if resp["result"]["Ok"].is_null() == false {
             block.hash    = resp["result"]["Ok"]["header"]["hash"].as_str().unwrap().to_string();
@@ -466,12 +515,18 @@ pub async fn get_block_data(height: &str, block: &mut Block)
             block.version = resp["result"]["Ok"]["header"]["version"].to_string();
 
             for kernel in resp["result"]["Ok"]["kernels"].as_array().unwrap() {
-                let fee = kernel["fee"].to_string().parse::<f64>().unwrap();
+                let fee  = kernel["fee"].to_string().parse::<f64>().unwrap();
+                let lock = KernelFeatures::from_printable(kernel).map(|f| f.lock()).unwrap_or_default();
                 block.kernels.push((kernel["excess"].as_str().unwrap().to_string(),
                                     kernel["features"].as_str().unwrap().to_string(),
-                                    (fee / 1000000000.0).to_string()));
+                                    (fee / 1000000000.0).to_string(),
+                                    lock));
                 block.fees += fee;
                 block.weight += KERNEL_WEIGHT;
+
+                if verify::kernel_signature(kernel) {
+                    block.sigs_ok += 1;
+                }
             }
 
             for input in resp["result"]["Ok"]["inputs"].as_array().unwrap() {
@@ -485,6 +540,14 @@ pub async fn get_block_data(height: &str, block: &mut Block)
                 block.weight += OUTPUT_WEIGHT;
             }
 
+            block.reward = schedule::BLOCK_REWARD as f64 + block.fees / 1000000000.0;
+            block.cb_out = block.outputs.iter().find(|o| o.1 == "Coinbase").map(|o| o.0.clone()).unwrap_or_default();
+            block.cb_ker = block.kernels.iter().find(|k| k.1 == "Coinbase").map(|k| k.0.clone()).unwrap_or_default();
+
+            if let (Ok(tip), Ok(height)) = (get_current_height(net).parse::<u64>(), block.height.parse::<u64>()) {
+                block.maturity = COINBASE_MATURITY.saturating_sub(tip.saturating_sub(height) + 1);
+            }
+
             block.weight   = format!("{:.2}", block.weight / 40000.0 * 100.0).parse::<f64>().unwrap();
             block.ker_len  = block.kernels.iter().count() as u64;
             block.in_len   = block.inputs.iter().count() as u64;
@@ -507,12 +570,42 @@ pub async fn get_block_data(height: &str, block: &mut Block)
 }
 
 
+// Verifying block kernel sums, the kernel offset of the block is relative to the previous block
+// total kernel offset, so this costs an extra header call and is only done on request.
+pub async fn get_block_sums(net: &Network, height: &str, block: &mut Block)
+             -> Result<(), anyhow::Error> {
+    let height = height.parse::<u64>()?;
+    let params = &format!("[{}, null, null]", height)[..];
+    let resp   = call(net, "get_block", params, "1", "foreign").await?;
+
+    if resp["result"]["Ok"].is_null() {
+        return Ok(());
+    }
+
+    let prev_offset = if height == 0 {
+        format!("{:064}", 0)
+    } else {
+        let params = &format!("[{}, null, null]", height - 1)[..];
+        let prev   = call(net, "get_header", params, "1", "foreign").await?;
+
+        match prev["result"]["Ok"]["total_kernel_offset"].as_str() {
+            Some(offset) => offset.to_string(),
+            None         => return Ok(()),
+        }
+    };
+
+    block.sums_ok = Some(verify::kernel_sums(&resp["result"]["Ok"], &prev_offset));
+
+    Ok(())
+}
+
+
 // Get block height by hash.
-pub async fn get_block_header(hash: &str, height: &mut String)
+pub async fn get_block_header(net: &Network, hash: &str, height: &mut String)
              -> Result<(), anyhow::Error> {
     let params = &format!("[null, \"{}\", null]", hash)[..];
 
-    let resp = call("get_header", params, "1", "foreign").await?;
+    let resp = call(net, "get_header", params, "1", "foreign").await?;
     
     if resp["result"]["Ok"].is_null() == false {
         *height = resp["result"]["Ok"]["height"].to_string();
@@ -522,10 +615,26 @@ pub async fn get_block_header(hash: &str, height: &mut String)
 }
 
 
+// Push a transaction to the node, node errors are returned as they are.
+pub async fn push_transaction(net: &Network, tx: &Value, fluff: bool) -> Result<(), anyhow::Error> {
+    let params = serde_json::to_string(&(tx, fluff))?;
+    let resp   = call(net, "push_transaction", &params, "1", "foreign").await?;
+
+    if resp["result"].get("Ok").is_some() {
+        return Ok(());
+    }
+
+    match resp["result"].get("Err") {
+        Some(err) => bail!("{}", err.as_str().map(|e| e.to_string()).unwrap_or(err.to_string())),
+        None      => bail!("{}", resp["error"]["message"].as_str().unwrap_or("push_transaction failed")),
+    }
+}
+
+
 // Get output.
-pub async fn get_output(commit: &str, output: &mut Output) -> Result<(), anyhow::Error> {
+pub async fn get_output(net: &Network, commit: &str, output: &mut Output) -> Result<(), anyhow::Error> {
     // First check whether output is broadcasted but not confirmed yet (in mempool)
-    let mut resp = call("get_unconfirmed_transactions", "[]", "1", "foreign").await?;
+    let mut resp = call(net, "get_unconfirmed_transactions", "[]", "1", "foreign").await?;
 
     if resp["result"]["Ok"].is_null() == false {
         for tx in resp["result"]["Ok"].as_array().unwrap() {
@@ -535,6 +644,7 @@ pub async fn get_output(commit: &str, output: &mut Output) -> Result<(), anyhow:
                     output.out_type = "Plain".to_string();
                     output.commit   = out["commit"].as_str().unwrap().to_string();
                     output.status   = "Unconfirmed".to_string();
+                    output.proof    = out["proof"].as_str().unwrap_or_default().to_string();
                     // Found it, no need to continue
                     return Ok(());
                 }
@@ -544,7 +654,7 @@ pub async fn get_output(commit: &str, output: &mut Output) -> Result<(), anyhow:
 
     let params = &format!("[[\"{}\"], null, null, true, true]", commit)[..];
 
-    resp = call("get_outputs", params, "1", "foreign").await?;
+    resp = call(net, "get_outputs", params, "1", "foreign").await?;
 
     if resp["result"]["Ok"][0].is_null() == false {
         output.height   = resp["result"]["Ok"][0]["block_height"].to_string();
@@ -552,13 +662,34 @@ pub async fn get_output(commit: &str, output: &mut Output) -> Result<(), anyhow:
         output.out_type = resp["result"]["Ok"][0]["output_type"].as_str().unwrap().to_string();
         output.raw_data = serde_json::to_string_pretty(&resp).unwrap();
 
-        let resp_status = call("get_status", "[]", "1", "owner").await?;
+        let out = &resp["result"]["Ok"][0];
+
+        output.proof        = out["proof"].as_str().unwrap_or_default().to_string();
+        output.proof_hash   = out["proof_hash"].as_str().unwrap_or_default().to_string();
+        output.mmr_index    = out["mmr_index"].to_string();
+        output.merkle_proof = out["merkle_proof"].as_str().unwrap_or_default().to_string();
+
+        if let Some(indexed) = indexer::output(net, commit) {
+            output.hash = indexed.hash;
+        }
+
+        let resp_status = call(net, "get_status", "[]", "1", "owner").await?;
 
         if resp_status != Value::Null {
             let curr_height = resp_status["result"]["Ok"]["tip"]["height"].to_string();
             let num_conf    = curr_height.parse::<u64>().unwrap() - output.height.parse::<u64>().unwrap() + 1;
 
             output.status = format!("{} Confirmations", num_conf.to_string());
+
+            if output.out_type == "Coinbase" {
+                output.maturity = COINBASE_MATURITY.saturating_sub(num_conf);
+            }
+        }
+    } else if let Some(indexed) = indexer::output(net, commit) {
+        // Spent outputs are only known to the chain index
+        if !indexed.spent_height.is_empty() {
+            *output = indexed;
+            output.status = "Spent".to_string();
         }
     }
 
@@ -567,21 +698,23 @@ pub async fn get_output(commit: &str, output: &mut Output) -> Result<(), anyhow:
 
 
 // Get kernel.
-pub async fn get_kernel(excess: &str, kernel: &mut Kernel) -> Result<(), anyhow::Error> {
+pub async fn get_kernel(net: &Network, excess: &str, kernel: &mut Kernel) -> Result<(), anyhow::Error> {
     // First check whether kernel is broadcasted but not confirmed yet (in mempool)
-    let mut resp = call("get_unconfirmed_transactions", "[]", "1", "foreign").await?;
+    let mut resp = call(net, "get_unconfirmed_transactions", "[]", "1", "foreign").await?;
     
     if resp["result"]["Ok"].is_null() == false {
         for tx in resp["result"]["Ok"].as_array().unwrap() {
             for ker in tx["tx"]["body"]["kernels"].as_array().unwrap() {
                 if ker["excess"].as_str().unwrap() == excess {
-                    // Only Plain kernels in the mempool
-                    kernel.ker_type = "Plain".to_string();
                     kernel.excess   = ker["excess"].as_str().unwrap().to_string();
                     kernel.status   = "Unconfirmed".to_string();
-                    kernel.fee      = format!("ツ {}",
-                                      ker["features"]["Plain"]["fee"]
-                                      .to_string().parse::<f64>().unwrap() / 1000000000.0);
+                    kernel.features = KernelFeatures::from_json(&ker["features"]);
+                    kernel.verified = Some(verify::kernel_signature(ker));
+
+                    if let Some(features) = &kernel.features {
+                        kernel.ker_type = features.name().to_string();
+                        kernel.fee      = format!("ツ {}", features.fee() as f64 / 1000000000.0);
+                    }
                     // Found it, no need to continue
                     return Ok(());
                 }
@@ -591,23 +724,29 @@ pub async fn get_kernel(excess: &str, kernel: &mut Kernel) -> Result<(), anyhow:
     
     let params = &format!("[\"{}\", null, null]", excess)[..];
 
-    resp = call("get_kernel", params, "1", "foreign").await?;
+    resp = call(net, "get_kernel", params, "1", "foreign").await?;
     
     if resp["result"]["Ok"].is_null() == false {
         kernel.height = resp["result"]["Ok"]["height"].to_string();
-        kernel.excess = resp["result"]["Ok"]["tx_kernel"]["excess"].as_str().unwrap().to_string();
-        if resp["result"]["Ok"]["tx_kernel"]["features"]["Plain"].is_null() == false {
-            kernel.ker_type = "Plain".to_string();
-            kernel.fee      = format!("ツ {}",
-                                      resp["result"]["Ok"]["tx_kernel"]["features"]["Plain"]["fee"]
-                                      .to_string().parse::<f64>().unwrap() / 1000000000.0);
-        } else {
-            kernel.ker_type = resp["result"]["Ok"]["tx_kernel"]["features"].as_str().unwrap().to_string();
+        kernel.excess   = resp["result"]["Ok"]["tx_kernel"]["excess"].as_str().unwrap().to_string();
+        kernel.features = KernelFeatures::from_json(&resp["result"]["Ok"]["tx_kernel"]["features"]);
+        kernel.verified = Some(verify::kernel_signature(&resp["result"]["Ok"]["tx_kernel"]));
+
+        if let Some(features) = &kernel.features {
+            kernel.ker_type = features.name().to_string();
+            kernel.fee      = format!("ツ {}", features.fee() as f64 / 1000000000.0);
+
+            // NRD kernels may reuse the excess, listing other kernels found in the index
+            if let KernelFeatures::NoRecentDuplicate { .. } = features {
+                kernel.duplicates = indexer::kernel_heights(net, excess).into_iter()
+                                    .filter(|h| h.to_string() != kernel.height)
+                                    .collect();
+            }
         }
 
         kernel.raw_data = serde_json::to_string_pretty(&resp).unwrap();
         
-        let resp_status = call("get_status", "[]", "1", "owner").await?;
+        let resp_status = call(net, "get_status", "[]", "1", "owner").await?;
 
         if resp_status != Value::Null {
             let curr_height = resp_status["result"]["Ok"]["tip"]["height"].to_string();
@@ -622,20 +761,30 @@ pub async fn get_kernel(excess: &str, kernel: &mut Kernel) -> Result<(), anyhow:
 
 
 // Collecting block kernels for transactions stats.
-pub async fn get_block_kernels(height: &String, blocks: &mut Vec<Block>)
+pub async fn get_block_kernels(net: &Network, height: &String, blocks: &mut Vec<Block>)
              -> Result<(), anyhow::Error> {
     if height.is_empty() == false {
         let params = &format!("[{}, {}, 720, false]", height.parse::<u64>().unwrap() - 720,
                               height)[..];
-        let resp   = call("get_blocks", params, "1", "foreign").await?;
+        let resp   = call(net, "get_blocks", params, "1", "foreign").await?;
 
         for resp_block in resp["result"]["Ok"]["blocks"].as_array().unwrap() {
             let mut block = Block::new();
+
+            block.height = resp_block["header"]["height"].to_string();
             
             for kernel in resp_block["kernels"].as_array().unwrap() {
                 block.kernels.push((kernel["excess"].to_string(),
                                     kernel["features"].as_str().unwrap().to_string(),
-                                    kernel["fee"].to_string()));
+                                    kernel["fee"].to_string(),
+                                    String::new()));
+                block.fees += kernel["fee"].as_f64().unwrap_or_default();
+            }
+
+            for output in resp_block["outputs"].as_array().unwrap() {
+                if output["output_type"].as_str() == Some("Coinbase") {
+                    block.cb_out = output["commit"].as_str().unwrap_or_default().to_string();
+                }
             }
             
             blocks.push(block);
@@ -647,18 +796,17 @@ pub async fn get_block_kernels(height: &String, blocks: &mut Vec<Block>)
 
 
 // Collecting: period_1h, period_24h, fees_1h, fees_24h.
-pub async fn get_txn_stats(dashboard: Arc<Mutex<Dashboard>>,
-                           transactions: Arc<Mutex<Transactions>>)-> Result<(), Error> {
+pub async fn get_txn_stats(net: &Network)-> Result<(), Error> {
     let mut blocks = Vec::<Block>::new();
-    let height     = get_current_height(dashboard.clone());
+    let height     = get_current_height(net);
 
     if height.is_empty() == false && height.parse::<u64>().unwrap() > 1440 {
         // get_blocks grin rpc has limit of maximum of 1000 blocks request
         // https://github.com/mimblewimble/grin/blob/master/api/src/handlers/blocks_api.rs#L27
         // So, collecting kernels 2 times by 720 blocks to get a day of blocks
-        let _ = get_block_kernels(&((height.parse::<u64>().unwrap() - 720).to_string()), &mut blocks)
+        let _ = get_block_kernels(net, &((height.parse::<u64>().unwrap() - 720).to_string()), &mut blocks)
                                  .await;
-        let _ = get_block_kernels(&height, &mut blocks).await;
+        let _ = get_block_kernels(net, &height, &mut blocks).await;
 
         if blocks.is_empty() == false {
             let mut ker_count_1h  = 0;
@@ -666,8 +814,18 @@ pub async fn get_txn_stats(dashboard: Arc<Mutex<Dashboard>>,
             let mut fees_1h       = 0.0;
             let mut fees_24h      = 0.0;
             let mut index         = 0;
+            let mut coinbase      = Vec::<(u64, String, f64)>::new();
+            let tip               = height.parse::<u64>().unwrap();
 
             for block in blocks {
+                // Coinbase outputs that can't be spent yet
+                if let Ok(block_height) = block.height.parse::<u64>() {
+                    if tip - block_height + 1 < COINBASE_MATURITY && !coinbase.iter().any(|c| c.0 == block_height) {
+                        coinbase.push((block_height, block.cb_out.clone(),
+                                       schedule::BLOCK_REWARD as f64 + block.fees / 1000000000.0));
+                    }
+                }
+
                 // Latest 60 blocks
                 if index >= 1380 {
                     for kernel in block.kernels.clone() {
@@ -688,12 +846,15 @@ pub async fn get_txn_stats(dashboard: Arc<Mutex<Dashboard>>,
                 index = index + 1;
             }
 
-            let mut txns = transactions.lock().unwrap();
+            let mut txns = net.txns.lock().unwrap();
 
             txns.period_1h  = ker_count_1h.to_string();
             txns.period_24h = ker_count_24h.to_string();
             txns.fees_1h    = format!("{:.2}", fees_1h / 1000000000.0);
             txns.fees_24h   = format!("{:.2}", fees_24h / 1000000000.0);
+
+            coinbase.sort_by_key(|c| Reverse(c.0));
+            txns.coinbase   = coinbase;
         }
     }
 
@@ -702,18 +863,17 @@ pub async fn get_txn_stats(dashboard: Arc<Mutex<Dashboard>>,
 
 
 // Return current block height
-pub fn get_current_height(dashboard: Arc<Mutex<Dashboard>>) -> String {
-    let data = dashboard.lock().unwrap();
+pub fn get_current_height(net: &Network) -> String {
+    let data = net.dash.lock().unwrap();
 
     data.height.clone()
 }
 
 
 // Collecting recent blocks data.
-pub async fn get_recent_blocks(dashboard: Arc<Mutex<Dashboard>>,
-                         blocks: Arc<Mutex<Vec<Block>>>) -> Result<(), Error> {
+pub async fn get_recent_blocks(net: &Network) -> Result<(), Error> {
     let mut i      = 0;
-    let height_str = get_current_height(dashboard.clone());
+    let height_str = get_current_height(net);
 
     if height_str.is_empty() == false && height_str.parse::<u64>().unwrap() > 0 {
         let height         = height_str.parse::<u64>().unwrap();
@@ -723,13 +883,13 @@ pub async fn get_recent_blocks(dashboard: Arc<Mutex<Dashboard>>,
             let mut block = Block::new();
             let height_index = height - i;
 
-            let _ = get_block_list_data(&height_index.to_string(), &mut block).await;
+            let _ = get_block_list_data(net, &height_index.to_string(), &mut block).await;
 
             blocks_vec.push(block);
             i = i + 1;
         }
 
-        let mut blcks = blocks.lock().unwrap();
+        let mut blcks = net.blocks.lock().unwrap();
         blcks.clear();
         *blcks = blocks_vec;
 
@@ -740,12 +900,12 @@ pub async fn get_recent_blocks(dashboard: Arc<Mutex<Dashboard>>,
 
 
 // Collecting a specified list of blocks.
-pub async fn get_block_list_by_height(height: &str, blocks: &mut Vec<Block>,
+pub async fn get_block_list_by_height(net: &Network, height: &str, blocks: &mut Vec<Block>,
                                       latest_height: &mut u64) -> Result<(), anyhow::Error> {
     let mut i      = 0;
     let height = height.to_string();
 
-    let resp = call("get_status", "[]", "1", "owner").await?;
+    let resp = call(net, "get_status", "[]", "1", "owner").await?;
 
     if resp != Value::Null {
         *latest_height = resp["result"]["Ok"]["tip"]["height"].to_string().parse::<u64>().unwrap();
@@ -760,7 +920,7 @@ pub async fn get_block_list_by_height(height: &str, blocks: &mut Vec<Block>,
             while i < 10 {
                 let mut block = Block::new();
 
-                let _ = get_block_list_data(&(height - i).to_string(), &mut block).await;
+                let _ = get_block_list_data(net, &(height - i).to_string(), &mut block).await;
 
                 blocks.push(block);
                 i = i + 1;
@@ -772,13 +932,13 @@ pub async fn get_block_list_by_height(height: &str, blocks: &mut Vec<Block>,
 }
 
 // Collecting unspent outputs.
-pub async fn get_unspent_outputs(dashboard: Arc<Mutex<Dashboard>>) -> Result<(), anyhow::Error> {
+pub async fn get_unspent_outputs(net: &Network) -> Result<(), anyhow::Error> {
     let mut highest_mmr_index = 0;
     let mut current_mmr_index = 0;
     let mut utxo_count        = 0;
 
     // Get the highest MMR index
-    let resp = call("get_unspent_outputs", "[1, null, 10000, false]", "1", "foreign").await?;
+    let resp = call(net, "get_unspent_outputs", "[1, null, 10000, false]", "1", "foreign").await?;
 
     if resp != Value::Null {
         highest_mmr_index = resp["result"]["Ok"]["highest_index"].to_string().parse::<u64>().unwrap();
@@ -790,7 +950,7 @@ pub async fn get_unspent_outputs(dashboard: Arc<Mutex<Dashboard>>) -> Result<(),
         current_mmr_index = current_mmr_index + 1;
         let params = &format!("[{}, {}, 10000, false]", current_mmr_index, highest_mmr_index)[..];
 
-        let resp = call("get_unspent_outputs", params, "1", "foreign").await?;
+        let resp = call(net, "get_unspent_outputs", params, "1", "foreign").await?;
     
         if resp != Value::Null {
             if resp["result"]["Ok"]["outputs"] != Value::Null {
@@ -805,18 +965,37 @@ pub async fn get_unspent_outputs(dashboard: Arc<Mutex<Dashboard>>) -> Result<(),
         }
     }
 
-    let mut data = dashboard.lock().unwrap();
+    let mut data = net.dash.lock().unwrap();
 
     data.utxo_count = utxo_count.to_string();
 
     Ok(())
 }
 
-// Get public nodes data
-pub async fn get_pubnodes_stats(netstats: Arc<Mutex<NetStats>>) -> Result<(), anyhow::Error> {
+// Public nodes monitor history, in days.
+const PUBNODE_DAYS: i64 = 30;
+
+
+// First day of the public nodes monitor history (YYYY-MM-DD).
+pub fn pubnode_since() -> String {
+    (Utc::now() - TimeDelta::days(PUBNODE_DAYS - 1)).format("%Y-%m-%d").to_string()
+}
+
+
+// Get public nodes data, once the monitor interval has passed.
+// Every probe is recorded, nodes behind our tip or on another chain are flagged.
+pub async fn get_pubnodes_stats(net: &Network) -> Result<(), anyhow::Error> {
+    let now = Utc::now().timestamp();
+
+    if now - net.pub_polled.load(Ordering::Relaxed) < net.config.pubnode_interval {
+        return Ok(());
+    }
+
+    net.pub_polled.store(now, Ordering::Relaxed);
+
     let mut nodes = Vec::<PublicNode>::new();
 
-    for endpoint in CONFIG.public_nodes.clone() {
+    for endpoint in net.config.public_nodes.clone() {
         let mut node = PublicNode::new();
 
         node.name = endpoint
@@ -830,117 +1009,146 @@ pub async fn get_pubnodes_stats(netstats: Arc<Mutex<NetStats>>) -> Result<(), an
 
         match call_external("get_version", "[]", "1", "foreign", endpoint.clone()).await {
             Ok(resp) => {
-                            if resp != Value::Null && resp["result"]["Ok"].is_null() == false {
-                                node.version = resp["result"]["Ok"]["node_version"].as_str().unwrap().to_string();
+                            if resp != Value::Null && !resp["result"]["Ok"].is_null() {
+                                node.version = resp["result"]["Ok"]["node_version"].as_str().unwrap_or_default().to_string();
                             }
                         },
-            Err(e)   => {
-                            warn!("{}", e);
-                            continue;
-                        },
+            Err(e)   => warn!("{}", e),
         }
 
+        let start = Instant::now();
+
         match call_external("get_tip", "[]", "1", "foreign", endpoint).await {
             Ok(resp) => {
-                            if resp != Value::Null && resp["result"]["Ok"].is_null() == false {
-                                node.height = resp["result"]["Ok"]["height"].to_string();
-                                node.hash = resp["result"]["Ok"]["last_block_pushed"].as_str().unwrap().to_string();
+                            if resp != Value::Null && !resp["result"]["Ok"].is_null() {
+                                node.online  = true;
+                                node.latency = start.elapsed().as_millis() as u64;
+                                node.height  = resp["result"]["Ok"]["height"].to_string();
+                                node.hash    = resp["result"]["Ok"]["last_block_pushed"].as_str().unwrap_or_default().to_string();
                             }
                         },
-            Err(e)   => {
-                            warn!("{}", e);
-                            continue;
-                        },
+            Err(e)   => warn!("{}", e),
         }
+
+        node.status = get_pubnode_status(net, &node).await;
+
         nodes.push(node);
     }
 
-    let mut network = netstats.lock().unwrap();
+    let today = Utc::now().format("%Y-%m-%d").to_string();
+    let since = pubnode_since();
 
-    network.pub_nodes = nodes.clone();
+    if !net.config.database.is_empty() {
+        let conn = database::open_db_connection(&net.config.database)?;
 
-    Ok(())
-}
+        for node in nodes.iter() {
+            database::save_pubnode_probe(&conn, now, node)?;
+        }
+
+        database::prune_pubnode_probes(&conn, &since)?;
+    }
 
+    let mut network = net.netstats.lock().unwrap();
 
-pub async fn get_reachable_nodes(netstats: Arc<Mutex<NetStats>>) -> Result<(), anyhow::Error> {
-    let conn_nodes      = get_conn_nodes(netstats.clone());
-    let mut reach_nodes = get_reach_nodes(netstats.clone());
+    network.pub_days.retain(|d| d.date >= since);
 
-    // Update current list of reachable nodes
-    for node in reach_nodes.clone() {
-        let socket_addr: SocketAddr = match node.address.parse() {
-            Ok(addr) => addr,
-            Err(_)   => continue,
+    for node in nodes.iter_mut() {
+        let day = match network.pub_days.iter().position(|d| d.name == node.name && d.date == today) {
+            Some(i) => &mut network.pub_days[i],
+            None    => {
+                           network.pub_days.push(PubNodeDay { name: node.name.clone(), date: today.clone(), probes: 0, online: 0, latency: 0 });
+                           network.pub_days.last_mut().unwrap()
+                       },
         };
 
-        // Attempt to connect with a timeout
-        match TcpStream::connect_timeout(&socket_addr, Duration::from_millis(3000)) {
-            Ok(_) => continue,
-            Err(_) => reach_nodes.retain(|value| value.address != node.address),
+        day.probes += 1;
+
+        if node.online {
+            day.online  += 1;
+            day.latency += node.latency;
         }
+
+        let days: Vec<&PubNodeDay> = network.pub_days.iter().filter(|d| d.name == node.name).collect();
+        let probes: u32            = days.iter().map(|d| d.probes).sum();
+        let online: u32            = days.iter().map(|d| d.online).sum();
+        let latency: u64           = days.iter().map(|d| d.latency).sum();
+
+        node.uptime      = format!("{:.1}", online as f64 * 100.0 / probes as f64);
+        node.avg_latency = if online > 0 { (latency / online as u64).to_string() } else { String::new() };
+        node.history     = (0..PUBNODE_DAYS).rev()
+                           .map(|i| (Utc::now() - TimeDelta::days(i)).format("%Y-%m-%d").to_string())
+                           .map(|date| match days.iter().find(|d| d.date == date) {
+                               Some(d) => format!("{:.0}", d.online as f64 * 100.0 / d.probes as f64),
+                               None    => String::new(),
+                           })
+                           .collect();
     }
 
-    // Loop over connected nodes and add to reachable nodes list if applicable
-    for mut node in conn_nodes.clone() {
-        let socket_addr: SocketAddr = match node.address.parse() {
-            Ok(addr) => addr,
-            Err(_)   => continue,
-        };
+    network.pub_nodes = nodes;
 
-        // Attempt to connect with a timeout
-        match TcpStream::connect_timeout(&socket_addr, Duration::from_millis(3000)) {
-            Ok(_) => {
-                         let client = reqwest::Client::new();
-                         if let Some((addr, _port)) = node.address.rsplit_once(':') {
-                             let ip = addr.trim_matches(|c| c == '[' || c == ']');
-                             //let url = format!("https://api.country.is/{}", ip);
-                             let url = format!("http://ip-api.com/json/{}", ip);
-
-                             let resp: Value = client.get(&url).send().await?.json().await?;
-                             if resp != Value::Null && resp["status"] == "success" {
-                                 if let Some(code) = resp["countryCode"].as_str() {
-                                     node.location = resp["country"].as_str().unwrap().to_string();
-                                     node.isp      = resp["isp"].as_str().unwrap().to_string();
-                                     node.flag     = code_to_flag(code).unwrap().to_string();
-                                 }
-                             }
-                         }
-
-                         if let Some(item) = reach_nodes.iter_mut().find(|x| x.address == node.address) {
-                             // If address exists, then update with new data
-                             *item = node.clone();
-                         } else {
-                             // If it doesn't exist, add it
-                             reach_nodes.push(node.clone());
-                         }
-                     },
-            Err(_) => continue,
-        }
+    Ok(())
+}
+
+
+// Comparing public node tip with our chain: "offline", "behind" (more than max_height_lag blocks),
+// "forked" (different block at the same height) or empty if the node is fine.
+async fn get_pubnode_status(net: &Network, node: &PublicNode) -> String {
+    if !node.online {
+        return "offline".to_string();
     }
 
-    let mut nstats = netstats.lock().unwrap();
+    let (height, tip_hash) = {
+        let dash = net.dash.lock().unwrap();
+        (dash.height.parse::<u64>().unwrap_or(0), dash.tip_hash.clone())
+    };
+    let node_height = node.height.parse::<u64>().unwrap_or(0);
 
-    nstats.reach_nodes.clear();
-    
-    // Copying updated list of reachable nodes
-    nstats.reach_nodes = reach_nodes.clone();
+    // Our node is not ready or the public node is ahead of it
+    if height == 0 || node_height > height {
+        return String::new();
+    }
 
-    Ok(())
+    if node_height == height {
+        return if node.hash != tip_hash { "forked".to_string() } else { String::new() };
+    }
+
+    let params = &format!("[{}, null, null]", node_height)[..];
+
+    if let Ok(resp) = call(net, "get_header", params, "1", "foreign").await {
+        if resp["result"]["Ok"]["hash"].as_str().is_some_and(|hash| hash != node.hash) {
+            return "forked".to_string();
+        }
+    }
+
+    if node_height + net.config.max_height_lag < height {
+        "behind".to_string()
+    } else {
+        String::new()
+    }
 }
 
 
-pub fn get_conn_nodes(netstats: Arc<Mutex<NetStats>>) -> Vec<ConnectedNode> {
-    let nstats = netstats.lock().unwrap();
+pub fn get_conn_nodes(net: &Network) -> Vec<ConnectedNode> {
+    let nstats = net.netstats.lock().unwrap();
 
     nstats.conn_nodes.clone()
 }
 
 
-pub fn get_reach_nodes(netstats: Arc<Mutex<NetStats>>) -> Vec<ConnectedNode> {
-    let nstats = netstats.lock().unwrap();
 
-    nstats.reach_nodes.clone()
-}
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rocket::async_test]
+    async fn connect_errors() {
+        // Nothing listens on port 1
+        let e = reqwest::Client::new().get("http://127.0.0.1:1").send().await.unwrap_err();
+
+        assert!(is_connect_error(&e.into()));
+        assert!(!is_connect_error(&anyhow!("operation timed out")));
+    }
+}
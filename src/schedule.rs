@@ -0,0 +1,260 @@
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+
+use crate::data::SupplyPoint;
+
+
+// Grin emission schedule: a constant reward of 60 grin per block, one block per minute.
+// The genesis block carries a reward too, transaction fees are paid to miners from existing coins
+// and are not part of the supply.
+pub const BLOCK_REWARD: u64 = 60;
+pub const BLOCK_TIME:   u64 = 60;
+// Julian year
+pub const YEAR:         u64 = 31557600;
+// Grin has no max supply, soft supply is used instead
+// https://john-tromp.medium.com/a-case-for-using-soft-total-supply-1169a188d153
+pub const SOFT_SUPPLY:  u64 = 3150000000;
+
+// Genesis block timestamps
+const GENESIS_MAIN: i64 = 1547568086; // 2019-01-15 16:01:26 UTC
+const GENESIS_TEST: i64 = 1546030084; // 2018-12-28 20:48:04 UTC
+
+
+// Chain tip (height and header timestamp) used to map heights to dates.
+#[derive(Clone, Copy)]
+pub struct Tip {
+    pub height: u64,
+    pub time:   i64,
+    pub chain:  &'static str,
+}
+
+impl Tip {
+    pub fn new(height: u64, time: i64, chain: &str) -> Tip {
+        Tip {
+            height,
+            // Tip time is not known before the first status update
+            time:  if time > 0 { time } else { Utc::now().timestamp() },
+            chain: if chain == "test" { "test" } else { "main" },
+        }
+    }
+
+    fn genesis(&self) -> i64 {
+        if self.chain == "test" { GENESIS_TEST } else { GENESIS_MAIN }
+    }
+}
+
+
+// Supply at a height, in grin.
+pub fn supply(height: u64) -> u64 {
+    (height + 1) * BLOCK_REWARD
+}
+
+
+// Coins emitted in a year.
+pub fn yearly_emission() -> u64 {
+    YEAR / BLOCK_TIME * BLOCK_REWARD
+}
+
+
+// Annual inflation at a height, in percent.
+pub fn inflation(height: u64) -> f64 {
+    yearly_emission() as f64 / supply(height) as f64 * 100.0
+}
+
+
+// Height at a time, the first block at or after the time from the chain (if the lookup knows it)
+// for past times, estimated otherwise.
+fn height_of(tip: &Tip, time: i64, lookup: &dyn Fn(i64) -> Option<u64>) -> u64 {
+    if time <= tip.time {
+        if let Some(height) = lookup(time) {
+            return height;
+        }
+    }
+
+    height_at(tip, time)
+}
+
+
+// Estimating the height at a time.
+// Past heights are interpolated between genesis and the tip (real block times drift from 60 seconds),
+// future heights are projected from the tip at the target block time.
+pub fn height_at(tip: &Tip, time: i64) -> u64 {
+    let genesis = tip.genesis();
+
+    if time <= genesis {
+        return 0;
+    }
+
+    if time <= tip.time && tip.time > genesis {
+        return (tip.height as f64 * (time - genesis) as f64 / (tip.time - genesis) as f64) as u64;
+    }
+
+    tip.height + (time - tip.time) as u64 / BLOCK_TIME
+}
+
+
+// Estimating the time of a height, the reverse of height_at.
+pub fn time_at(tip: &Tip, height: u64) -> i64 {
+    let genesis = tip.genesis();
+
+    if height <= tip.height && tip.height > 0 {
+        return genesis + ((tip.time - genesis) as f64 * height as f64 / tip.height as f64) as i64;
+    }
+
+    tip.time + ((height - tip.height) * BLOCK_TIME) as i64
+}
+
+
+// Supply at a height.
+pub fn at_height(tip: &Tip, height: u64) -> SupplyPoint {
+    SupplyPoint {
+        height,
+        date:      format_date(time_at(tip, height)),
+        supply:    supply(height),
+        inflation: inflation(height),
+        projected: height > tip.height,
+    }
+}
+
+
+// End of a day (YYYY-MM-DD), the last second of it.
+pub fn day_end(date: &str) -> Option<i64> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+
+    Some(Utc.from_utc_datetime(&day.and_hms_opt(23, 59, 59)?).timestamp())
+}
+
+
+// Supply at a date (YYYY-MM-DD), at the end of that day.
+// The height of the last block of the day is given for past dates if known, estimated otherwise.
+pub fn at_date(tip: &Tip, date: &str, height: Option<u64>) -> Option<SupplyPoint> {
+    let height = match height {
+        Some(height) => height,
+        None         => height_at(tip, day_end(date)?),
+    };
+
+    let mut point = at_height(tip, height);
+
+    point.date = date.to_string();
+
+    Some(point)
+}
+
+
+// Supply and inflation at the start of each quarter, from genesis to the given number of years ahead.
+// Lookup gives the first block at or after a time, if known.
+pub fn curve(tip: &Tip, years: i32, lookup: &dyn Fn(i64) -> Option<u64>) -> Vec<SupplyPoint> {
+    let start = DateTime::from_timestamp(tip.genesis(), 0).unwrap_or_default();
+    let end   = Utc::now().year() + years;
+    let mut points = Vec::new();
+
+    for year in start.year()..=end {
+        for month in [1, 4, 7, 10] {
+            let day = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).unwrap();
+
+            if day < start {
+                continue;
+            }
+
+            let mut point = at_height(tip, height_of(tip, day.timestamp(), lookup));
+
+            point.date = day.format("%Y-%m-%d").to_string();
+
+            points.push(point);
+        }
+    }
+
+    points
+}
+
+
+// Supply and inflation at the start of each year after genesis.
+pub fn by_year(tip: &Tip, years: i32, lookup: &dyn Fn(i64) -> Option<u64>) -> Vec<SupplyPoint> {
+    let start = DateTime::from_timestamp(tip.genesis(), 0).unwrap_or_default().year();
+
+    (start + 1..=start + years).map(|year| {
+        let day       = Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap();
+        let mut point = at_height(tip, height_of(tip, day.timestamp(), lookup));
+
+        point.date = year.to_string();
+
+        point
+    }).collect()
+}
+
+
+fn format_date(time: i64) -> String {
+    DateTime::from_timestamp(time, 0).unwrap_or_default().format("%Y-%m-%d").to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tip of a chain with exactly one block per minute since genesis.
+    fn tip(height: u64) -> Tip {
+        Tip { height, time: GENESIS_MAIN + (height * BLOCK_TIME) as i64, chain: "main" }
+    }
+
+    #[test]
+    fn supply_and_inflation() {
+        assert_eq!(supply(0), 60);
+        assert_eq!(supply(1000000), 60000060);
+        assert_eq!(yearly_emission(), 31557600);
+        assert_eq!(format!("{:.2}", inflation(1000000)), "52.60");
+        assert!(inflation(2000000) < inflation(1000000));
+    }
+
+    #[test]
+    fn heights_and_times() {
+        let tip = tip(1000);
+
+        assert_eq!(height_at(&tip, GENESIS_MAIN - 100), 0);
+        assert_eq!(height_at(&tip, GENESIS_MAIN + 500 * 60), 500);
+        assert_eq!(height_at(&tip, tip.time + 10 * 60), 1010);
+        assert_eq!(time_at(&tip, 500), GENESIS_MAIN + 500 * 60);
+        assert_eq!(time_at(&tip, 1010), tip.time + 10 * 60);
+        assert_eq!(Tip::new(5, GENESIS_TEST + 300, "test").genesis(), GENESIS_TEST);
+        assert_eq!(Tip::new(5, GENESIS_TEST + 300, "test").time, GENESIS_TEST + 300);
+    }
+
+    #[test]
+    fn supply_points() {
+        let tip = tip(1000);
+
+        let point = at_height(&tip, 1000);
+        assert_eq!(point.supply, 60060);
+        assert_eq!(point.date, "2019-01-16");
+        assert!(!point.projected);
+        assert!(at_height(&tip, 1001).projected);
+
+        let point = at_date(&tip, "2019-01-15", None).unwrap();
+        assert_eq!(point.date, "2019-01-15");
+        assert_eq!(point.height, (86399 - 16 * 3600 - 60 - 26) / 60);
+        assert_eq!(at_date(&tip, "2019-01-15", Some(400)).unwrap().supply, 24060);
+        assert!(at_date(&tip, "15-01-2019", None).is_none());
+    }
+
+    #[test]
+    fn yearly_points() {
+        let points = by_year(&tip(1000), 3, &|_| None);
+
+        assert_eq!(points.iter().map(|p| p.date.as_str()).collect::<Vec<&str>>(), vec!["2020", "2021", "2022"]);
+        assert!(points.windows(2).all(|p| p[0].supply < p[1].supply));
+        assert_eq!(curve(&tip(1000), 0, &|_| None).first().map(|p| p.date.as_str()), Some("2019-04-01"));
+    }
+
+    #[test]
+    fn chain_lookup() {
+        // Two years of blocks, slower than the target block time
+        let tip   = Tip { height: 1000000, time: GENESIS_MAIN + 2 * YEAR as i64, chain: "main" };
+        let start = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap().timestamp();
+
+        let lookup = |time: i64| if time == start { Some(500123) } else { None };
+
+        assert_eq!(by_year(&tip, 1, &lookup)[0].height, 500123);
+        // Future times are projected from the tip
+        assert_eq!(height_of(&tip, tip.time + 600, &|_| Some(1)), 1000010);
+        assert_eq!(day_end("2019-01-15"), Some(GENESIS_MAIN - 16 * 3600 - 60 - 26 + 86399));
+    }
+}
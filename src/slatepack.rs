@@ -0,0 +1,443 @@
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::verify;
+
+
+// Finalized transaction decoding: transaction JSON, slate JSON (v4) or armored slatepack (unencrypted).
+// https://github.com/mimblewimble/grin-rfcs/blob/master/text/0015-slatepack.md
+// https://github.com/mimblewimble/grin-rfcs/blob/master/text/0013-slate-serialization.md
+
+const BASE58: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+
+// Slate data needed to build the transaction.
+struct Slate {
+    state:       String,
+    offset:      String,
+    fee:         u64,
+    features:    u8,
+    lock_height: u64,
+    // Participant public excess, public nonce and partial signature s value (big endian)
+    sigs:        Vec<(String, String, Option<Vec<u8>>)>,
+    // Commitment features (0 - plain, 1 - coinbase), commitment and range proof of outputs
+    coms:        Vec<(u8, String, Option<String>)>,
+}
+
+
+// Binary slate reader, integers are big endian.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos:  usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], anyhow::Error> {
+        let end   = self.pos.checked_add(len).ok_or_else(|| anyhow!("bad slate length"))?;
+        let bytes = self.data.get(self.pos..end).ok_or_else(|| anyhow!("unexpected end of slate"))?;
+
+        self.pos = end;
+
+        Ok(bytes)
+    }
+
+    fn uint(&mut self, len: usize) -> Result<u64, anyhow::Error> {
+        Ok(self.bytes(len)?.iter().fold(0, |n, b| (n << 8) | *b as u64))
+    }
+
+    fn hex(&mut self, len: usize) -> Result<String, anyhow::Error> {
+        Ok(verify::hex(self.bytes(len)?))
+    }
+}
+
+
+// Decoding an input into transaction JSON as accepted by push_transaction.
+pub fn decode(input: &str) -> Result<Value, anyhow::Error> {
+    let input = input.trim();
+
+    if input.starts_with("BEGINSLATEPACK") {
+        return slate_bin(&payload(&armor(input)?)?)?.transaction();
+    }
+
+    let value: Value = serde_json::from_str(input).map_err(|e| anyhow!("not a slatepack or JSON: {}", e))?;
+
+    if value["body"].is_object() {
+        Ok(value)
+    } else if value["tx"]["body"].is_object() {
+        Ok(value["tx"].clone())
+    } else if value["sigs"].is_array() {
+        slate_json(&value)?.transaction()
+    } else {
+        bail!("unknown JSON, expected a transaction or a slate")
+    }
+}
+
+
+// Base58 data between slatepack header and footer, with the first 4 bytes of double SHA256 of the data in front.
+fn armor(input: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let parts: Vec<&str> = input.split('.').collect();
+
+    if parts.len() < 3 || parts[0].trim() != "BEGINSLATEPACK" || parts[2].trim() != "ENDSLATEPACK" {
+        bail!("bad slatepack armor");
+    }
+
+    let words: String = parts[1].chars().filter(|c| !c.is_whitespace()).collect();
+    let data           = base58(&words).ok_or_else(|| anyhow!("bad slatepack encoding"))?;
+
+    if data.len() < 4 || Sha256::digest(Sha256::digest(&data[4..]))[..4] != data[..4] {
+        bail!("bad slatepack checksum");
+    }
+
+    Ok(data[4..].to_vec())
+}
+
+
+fn base58(data: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::new();
+
+    for c in data.bytes() {
+        let mut carry = BASE58.iter().position(|b| *b == c)? as u32;
+
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte  = carry as u8;
+            carry >>= 8;
+        }
+
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    // Leading ones are leading zero bytes
+    let zeros = data.bytes().take_while(|c| *c == b'1').count();
+
+    Some([vec![0; zeros], bytes].concat())
+}
+
+
+// Slate from the binary slatepack: version, mode, optional fields (skipped) and the payload.
+fn payload(data: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    let mut reader = Reader { data, pos: 0 };
+
+    let _version = reader.uint(2)?;
+
+    if reader.uint(1)? != 0 {
+        bail!("encrypted slatepack, the recipient wallet is needed to decrypt it");
+    }
+
+    let _flags = reader.uint(2)?;
+    let skip   = reader.uint(4)? as usize;
+
+    reader.bytes(skip)?;
+
+    let len = reader.uint(8)? as usize;
+
+    Ok(reader.bytes(len)?.to_vec())
+}
+
+
+// Binary slate (v4).
+fn slate_bin(data: &[u8]) -> Result<Slate, anyhow::Error> {
+    let mut reader = Reader { data, pos: 0 };
+
+    if reader.uint(2)? != 4 {
+        bail!("unsupported slate version");
+    }
+
+    let _header_version = reader.uint(2)?;
+    let _id             = reader.bytes(16)?;
+
+    let state = match reader.uint(1)? {
+        1 => "S1", 2 => "S2", 3 => "S3", 4 => "I1", 5 => "I2", 6 => "I3",
+        _ => "",
+    };
+    let offset = reader.hex(32)?;
+    let status = reader.uint(1)?;
+
+    if status & 0x01 > 0 {
+        reader.uint(1)?;
+    }
+
+    if status & 0x02 > 0 {
+        reader.uint(8)?;
+    }
+
+    let fee      = if status & 0x04 > 0 { reader.uint(8)? } else { 0 };
+    let features = if status & 0x08 > 0 { reader.uint(1)? as u8 } else { 0 };
+
+    if status & 0x10 > 0 {
+        reader.uint(8)?;
+    }
+
+    let mut sigs = Vec::new();
+
+    for _ in 0..reader.uint(1)? {
+        let has_part = reader.uint(1)? == 1;
+        let excess   = reader.hex(33)?;
+        let nonce    = reader.hex(33)?;
+        let part     = if has_part { Some(reader.bytes(64)?[32..].to_vec()) } else { None };
+
+        sigs.push((excess, nonce, part));
+    }
+
+    let mut coms = Vec::new();
+
+    for _ in 0..reader.uint(2)? {
+        let has_proof = reader.uint(1)? == 1;
+        let features  = reader.uint(1)? as u8;
+        let commit    = reader.hex(33)?;
+        let proof     = if has_proof {
+                            let len = reader.uint(8)? as usize;
+                            Some(reader.hex(len)?)
+                        } else {
+                            None
+                        };
+
+        coms.push((features, commit, proof));
+    }
+
+    // Payment proof
+    if reader.uint(1)? == 1 {
+        reader.bytes(64)?;
+
+        if reader.uint(1)? == 1 {
+            reader.bytes(64)?;
+        }
+    }
+
+    let lock_height = if features == 2 { reader.uint(8)? } else { 0 };
+
+    Ok(Slate { state: state.to_string(), offset, fee, features, lock_height, sigs, coms })
+}
+
+
+// Slate JSON (v4), partial signatures are in compact form.
+fn slate_json(slate: &Value) -> Result<Slate, anyhow::Error> {
+    if !slate["ver"].as_str().unwrap_or_default().starts_with("4:") {
+        bail!("unsupported slate version");
+    }
+
+    let number = |value: &Value| match value {
+        Value::String(number) => number.parse::<u64>().ok(),
+        number                => number.as_u64(),
+    };
+
+    let mut sigs = Vec::new();
+
+    for sig in slate["sigs"].as_array().unwrap_or(&Vec::new()) {
+        let part = match sig["part"].as_str() {
+            Some(part) => Some(verify::from_hex(part).filter(|p| p.len() == 64)
+                              .ok_or_else(|| anyhow!("bad partial signature"))?[32..].iter().rev().copied().collect()),
+            None       => None,
+        };
+
+        sigs.push((sig["xs"].as_str().unwrap_or_default().to_string(),
+                   sig["nonce"].as_str().unwrap_or_default().to_string(),
+                   part));
+    }
+
+    let coms = slate["coms"].as_array().unwrap_or(&Vec::new()).iter()
+               .map(|com| (com["f"].as_u64().unwrap_or_default() as u8,
+                           com["c"].as_str().unwrap_or_default().to_string(),
+                           com["p"].as_str().map(|p| p.to_string())))
+               .collect();
+
+    Ok(Slate {
+        state:       slate["sta"].as_str().unwrap_or_default().to_string(),
+        offset:      slate["off"].as_str().unwrap_or_default().to_string(),
+        fee:         number(&slate["fee"]).unwrap_or_default(),
+        features:    slate["feat"].as_u64().unwrap_or_default() as u8,
+        lock_height: number(&slate["feat_args"]["lock_hgt"]).unwrap_or_default(),
+        sigs,
+        coms,
+    })
+}
+
+
+impl Slate {
+    // Transaction of a finalized slate, the kernel is built from the participants data.
+    fn transaction(&self) -> Result<Value, anyhow::Error> {
+        if self.state != "S3" && self.state != "I3" {
+            bail!("slate is not finalized (state {})", self.state);
+        }
+
+        let parts = self.sigs.iter().map(|s| s.2.clone()).collect::<Option<Vec<Vec<u8>>>>()
+                    .ok_or_else(|| anyhow!("missing partial signatures"))?;
+
+        let (excess, sig) = verify::aggregate_kernel(&self.sigs.iter().map(|s| s.0.clone()).collect::<Vec<String>>(),
+                                                     &self.sigs.iter().map(|s| s.1.clone()).collect::<Vec<String>>(),
+                                                     &parts)
+                            .ok_or_else(|| anyhow!("bad participant data"))?;
+
+        let features = match self.features {
+            0 => json!({"Plain": {"fee": self.fee}}),
+            2 => json!({"HeightLocked": {"fee": self.fee, "lock_height": self.lock_height}}),
+            _ => bail!("unsupported kernel features {}", self.features),
+        };
+
+        let name = |features: u8| if features == 1 { "Coinbase" } else { "Plain" };
+
+        Ok(json!({
+            "offset": self.offset,
+            "body": {
+                "inputs":  self.coms.iter().filter(|c| c.2.is_none())
+                           .map(|c| json!({"features": name(c.0), "commit": c.1}))
+                           .collect::<Vec<Value>>(),
+                "outputs": self.coms.iter().filter(|c| c.2.is_some())
+                           .map(|c| json!({"features": name(c.0), "commit": c.1, "proof": c.2}))
+                           .collect::<Vec<Value>>(),
+                "kernels": [{"features": features, "excess": excess, "excess_sig": sig}],
+            },
+        }))
+    }
+}
+
+
+// Basic transaction checks before it is pushed: structure, kernel signatures and kernel sums.
+pub fn validate(tx: &Value) -> Result<(), anyhow::Error> {
+    let body = &tx["body"];
+
+    let (inputs, outputs, kernels) = match (body["inputs"].as_array(), body["outputs"].as_array(), body["kernels"].as_array()) {
+        (Some(inputs), Some(outputs), Some(kernels)) => (inputs, outputs, kernels),
+        _                                            => bail!("missing inputs, outputs or kernels"),
+    };
+
+    if kernels.is_empty() || outputs.is_empty() {
+        bail!("transaction needs at least one kernel and one output");
+    }
+
+    if inputs.is_empty() {
+        bail!("transaction has no inputs");
+    }
+
+    if outputs.iter().any(|o| o["proof"].as_str().unwrap_or_default().is_empty()) {
+        bail!("output without a range proof");
+    }
+
+    for kernel in kernels {
+        if !verify::kernel_signature(kernel) {
+            bail!("invalid kernel signature {}", kernel["excess"].as_str().unwrap_or_default());
+        }
+    }
+
+    if !verify::transaction_sums(tx) {
+        bail!("kernel sums do not match");
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Finalized two-party slate (S3) built by an independent reference implementation,
+    // as an armored binary slatepack and as slate JSON.
+    const SLATEPACK: &str = "BEGINSLATEPACK. ASPARVyopaARh6p vDJWAuyJf7R84je 3pLWfc775ucmTWo QLCkLP77RewACMT EF8MfaXeGzWpFn6 \
+                            nYtrreTnEHYJ5pQ nYXfSQPKaZ9mnWQ 87UdFgDnPqtxhcU QffxSv9SrQBKebW pCK2X3zeUkhg3vy VnnHj4YiaHzePDD \
+                            xQnf5SvLbVxfGoa RMn9aGT6tV7aA8b gZFp72HbumECJAx C7A39V7xKi82yvR DStXazaUUAup7Lu qXyYU4HTtpZHr9v \
+                            VJMsSAPRQpfXqsA rMB3dzR8rwujqq7 8gbpFVbFPdnkmBc K8j1xhYgUg9DcNp 5eyhCwVMz6D74jv NFKr59T49zGbRn8 \
+                            Nt9M46CJVZX7ZYo x8hRMvsXhwz5Kjv XgfyKg4JZfzTWMG rMi3xfcrNsEHMkF FXGTTqFti5bZmNm VctN7q3Mok3SzzV \
+                            yBsnvPfeXHJpWfL EZRnMBqK5dUkvYy Sinevm7DxKZCoUz Dbgtf2Mr6b24g5x aXseQzh9qVMW6MC GnhFodpm1TiDDk3 \
+                            e5jPFaz1bzdfmnD YJbgVbuMs8F5VLe Q9ws81FTENrrEkL 927ZTuomHoqf2cj 85F5NkSVVTMWqav empYiyVW5zMJKjk \
+                            xoBc8dBDRwFFgT9 TgYkxuiz2iRAT3A Ze4UNm7PStawfNs AJR5. ENDSLATEPACK.";
+
+    const SLATE_JSON: &str = r#"{
+        "ver": "4:3",
+        "id": "0436430c-2b02-624c-2032-570501212b00",
+        "sta": "S3",
+        "off": "0000000000000000000000000000000000000000000000000000000000000037",
+        "fee": "7000000",
+        "sigs": [
+            {
+                "xs": "03f01d6b9018ab421dd410404cb869072065522bf85734008f105cf385a023a80f",
+                "nonce": "03e1fe434d345bf33083abb6280f4f44ac5fb22934977813c20c015f2b43d3fab8",
+                "part": "fe99050a3a2d65c6336e559c75eb70ad7f6654f385b8075aed2c436082744226b5160d5529632f792806b128dd478bbe27bd72aa72bd9548b60e4df4d7cc9240"
+            },
+            {
+                "xs": "02d69a24950779b832152bd65a1cb0886590f2317b109dbce7262fdf20dfc2d319",
+                "nonce": "03c8edf725b59d9f370870a5dcd5e70b0baea493a5b85cb227c1ef6a8e8cdad8fb",
+                "part": "fe99050a3a2d65c6336e559c75eb70ad7f6654f385b8075aed2c43608274422601003abe47412c561f4721cf64b62bf1ab934f3aa1f8f9a99a38414c5fdd0686"
+            }
+        ],
+        "coms": [
+            {
+                "c": "086491304050353e3b829be0092abe3822c30dbbf3f43e4d40f9146ed0874d1dc9"
+            },
+            {
+                "c": "081992505f41053f05359a106f36e08e8bfd5cf80a1fe70a6576871c5dd8de6677",
+                "p": "abababababababab"
+            },
+            {
+                "c": "09cb6a8186188062b69dd063c3f9243265498c9a00dda3d0673596022b214d7242",
+                "p": "abababababababab"
+            }
+        ]
+    }"#;
+
+    const EXCESS: &str = "09c75c85c1ee17c1a256eff6bd592666cbc923170659d50bfadbd1074ef2167faf";
+
+    #[test]
+    fn base58_decoding() {
+        assert_eq!(base58("2NEpo7TZRRrLZSi2U"), Some(b"Hello World!".to_vec()));
+        assert_eq!(base58("115T"), Some(vec![0, 0, 1, 2]));
+        assert_eq!(base58(""), Some(Vec::new()));
+        // 0, O, I and l are not in the alphabet
+        assert_eq!(base58("2NEpo7TZRRrLZSi20"), None);
+        assert_eq!(base58("Il"), None);
+    }
+
+    #[test]
+    fn armor_checksum() {
+        assert!(armor(SLATEPACK).is_ok());
+        assert_eq!(armor(&SLATEPACK.replacen("QLCkLP77RewACMT", "QLCkLP77RewACMU", 1)).unwrap_err().to_string(),
+                   "bad slatepack checksum");
+        assert_eq!(armor(&SLATEPACK.replace("ENDSLATEPACK", "END")).unwrap_err().to_string(), "bad slatepack armor");
+        assert_eq!(armor("BEGINSLATEPACK. 0OIl. ENDSLATEPACK.").unwrap_err().to_string(), "bad slatepack encoding");
+    }
+
+    #[test]
+    fn binary_slate() {
+        let tx = decode(SLATEPACK).unwrap();
+
+        assert_eq!(tx["body"]["kernels"][0]["excess"], EXCESS);
+        assert_eq!(tx["body"]["kernels"][0]["features"]["Plain"]["fee"], 7000000);
+        assert_eq!(tx["body"]["inputs"].as_array().unwrap().len(), 1);
+        assert_eq!(tx["body"]["outputs"].as_array().unwrap().len(), 2);
+        assert!(validate(&tx).is_ok());
+    }
+
+    #[test]
+    fn json_slate() {
+        let tx = decode(SLATE_JSON).unwrap();
+
+        assert_eq!(tx, decode(SLATEPACK).unwrap());
+        assert!(validate(&tx).is_ok());
+
+        // Transaction JSON as is, or wrapped in "tx"
+        assert_eq!(decode(&tx.to_string()).unwrap(), tx);
+        assert_eq!(decode(&json!({"tx": tx}).to_string()).unwrap(), tx);
+
+        // Fee changed after signing
+        let tampered = decode(&SLATE_JSON.replace("\"7000000\"", "\"7000001\"")).unwrap();
+
+        assert_eq!(validate(&tampered).unwrap_err().to_string(), format!("invalid kernel signature {}", EXCESS));
+
+        let unsigned = SLATE_JSON.replace("\"S3\"", "\"S2\"");
+
+        assert_eq!(decode(&unsigned).unwrap_err().to_string(), "slate is not finalized (state S2)");
+        assert_eq!(decode("{}").unwrap_err().to_string(), "unknown JSON, expected a transaction or a slate");
+    }
+
+    #[test]
+    fn reader_bounds() {
+        let mut reader = Reader { data: &[1, 2, 3], pos: 2 };
+
+        assert_eq!(reader.bytes(usize::MAX).unwrap_err().to_string(), "bad slate length");
+        assert_eq!(reader.bytes(2).unwrap_err().to_string(), "unexpected end of slate");
+        assert_eq!(reader.uint(1).unwrap(), 3);
+    }
+}
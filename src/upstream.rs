@@ -0,0 +1,91 @@
+use std::sync::atomic::Ordering;
+
+use crate::data::Upstream;
+use crate::networks::Network;
+
+
+// Whether the call can be spread over healthy upstreams.
+// Only read-only foreign calls are load balanced.
+pub fn is_balanced(net: &Network, method: &str, rpc_type: &str) -> bool {
+    net.config.load_balance == "enabled" && rpc_type == "foreign" && method.starts_with("get_")
+}
+
+
+// Returning upstreams in the order they should be tried.
+// Healthy nodes go first (primary first, or rotated for load balanced calls), unhealthy ones are the last resort.
+pub fn candidates(net: &Network, method: &str, rpc_type: &str) -> Vec<(usize, Upstream)> {
+    let nodes = net.upstreams.lock().unwrap();
+
+    let mut list: Vec<(usize, Upstream)> = nodes.iter().cloned().enumerate().filter(|(_, n)| n.healthy).collect();
+
+    if is_balanced(net, method, rpc_type) && list.len() > 1 {
+        let start = net.round_robin.fetch_add(1, Ordering::Relaxed) % list.len();
+
+        list.rotate_left(start);
+    }
+
+    list.extend(nodes.iter().cloned().enumerate().filter(|(_, n)| !n.healthy));
+
+    list
+}
+
+
+// Marking upstream as serving.
+pub fn set_active(net: &Network, index: usize) {
+    net.active.store(index, Ordering::Relaxed);
+}
+
+
+// Marking upstream as offline after a call failed to connect.
+pub fn set_offline(net: &Network, index: usize) {
+    let mut nodes = net.upstreams.lock().unwrap();
+
+    if let Some(node) = nodes.get_mut(index) {
+        node.healthy = false;
+        node.status  = "offline".to_string();
+    }
+}
+
+
+// Returning a copy of upstreams state.
+pub fn list(net: &Network) -> Vec<Upstream> {
+    net.upstreams.lock().unwrap().clone()
+}
+
+
+// Returning the upstream serving the latest call.
+pub fn active(net: &Network) -> Upstream {
+    let nodes = net.upstreams.lock().unwrap();
+
+    nodes[net.active.load(Ordering::Relaxed).min(nodes.len() - 1)].clone()
+}
+
+
+// Updating upstreams health with health check results (tip height and latency in ms).
+// Nodes lagging behind the highest tip are considered unhealthy.
+pub fn update(net: &Network, results: Vec<Option<(u64, u64)>>) {
+    let mut nodes = net.upstreams.lock().unwrap();
+
+    let max_height = results.iter().flatten().map(|(height, _)| *height).max().unwrap_or(0);
+
+    for (node, result) in nodes.iter_mut().zip(results) {
+        match result {
+            Some((height, latency)) => {
+                node.height  = height;
+                node.latency = latency;
+
+                if height + net.config.max_height_lag < max_height {
+                    node.healthy = false;
+                    node.status  = "behind".to_string();
+                } else {
+                    node.healthy = true;
+                    node.status  = "online".to_string();
+                }
+            },
+            None => {
+                node.healthy = false;
+                node.status  = "offline".to_string();
+            },
+        }
+    }
+}
@@ -0,0 +1,493 @@
+use bech32::Hrp;
+use blake2::{Blake2b, Digest};
+use blake2::digest::consts::U32;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use k256::{AffinePoint, EncodedPoint, FieldBytes, FieldElement, ProjectivePoint, Scalar, U256};
+use k256::elliptic_curve::PrimeField;
+use k256::elliptic_curve::ops::Reduce;
+use k256::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+use serde_json::Value;
+use sha2::Sha256;
+use sha3::Sha3_256;
+
+use crate::data::{KernelFeatures, PaymentProof};
+use crate::networks::Network;
+use crate::requests;
+use crate::schedule;
+
+
+// Kernel signature and block kernel sum checks, following secp256k1-zkp aggsig and pedersen modules.
+// https://github.com/mimblewimble/secp256k1-zkp
+
+// Generator H of the value part of Pedersen commitments.
+const GENERATOR_H: [u8; 65] = [
+    0x04,
+    0x50, 0x92, 0x9b, 0x74, 0xc1, 0xa0, 0x49, 0x54, 0xb7, 0x8b, 0x4b, 0x60, 0x35, 0xe9, 0x7a, 0x5e,
+    0x07, 0x8a, 0x5a, 0x0f, 0x28, 0xec, 0x96, 0xd5, 0x47, 0xbf, 0xee, 0x9a, 0xce, 0x80, 0x3a, 0xc0,
+    0x31, 0xd3, 0xc6, 0x86, 0x39, 0x73, 0x92, 0x6e, 0x04, 0x9e, 0x63, 0x7c, 0xb1, 0xb5, 0xf4, 0x0a,
+    0x36, 0xda, 0xc2, 0x8a, 0xf1, 0x76, 0x69, 0x68, 0xc3, 0x0c, 0x23, 0x13, 0xf3, 0xa3, 0x89, 0x04,
+];
+
+
+// Point of a commitment (or kernel excess), 0x08 prefix marks a quadratic residue y.
+fn commitment(hex: &str) -> Option<ProjectivePoint> {
+    let bytes = from_hex(hex)?;
+
+    if bytes.len() != 33 || (bytes[0] != 0x08 && bytes[0] != 0x09) {
+        return None;
+    }
+
+    let x = Option::<FieldElement>::from(FieldElement::from_bytes(FieldBytes::from_slice(&bytes[1..])))?;
+    // Square root is computed as a power of a square, so it is always the quadratic residue one
+    let y = Option::<FieldElement>::from((x.square() * x + FieldElement::from(7u64)).sqrt())?.normalize();
+    let y = if bytes[0] == 0x08 { y } else { y.negate(1).normalize() };
+
+    point(&x, &y)
+}
+
+
+fn point(x: &FieldElement, y: &FieldElement) -> Option<ProjectivePoint> {
+    let encoded = EncodedPoint::from_affine_coordinates(&x.to_bytes(), &y.to_bytes(), false);
+
+    Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&encoded)).map(ProjectivePoint::from)
+}
+
+
+fn generator_h() -> ProjectivePoint {
+    let encoded = EncodedPoint::from_bytes(GENERATOR_H).unwrap();
+
+    ProjectivePoint::from(AffinePoint::from_encoded_point(&encoded).unwrap())
+}
+
+
+// Coordinates of a point, None for the point at infinity.
+fn coordinates(point: &ProjectivePoint) -> Option<(FieldElement, FieldElement)> {
+    let encoded = point.to_affine().to_encoded_point(false);
+    let x       = Option::<FieldElement>::from(FieldElement::from_bytes(encoded.x()?))?;
+    let y       = Option::<FieldElement>::from(FieldElement::from_bytes(encoded.y()?))?;
+
+    Some((x, y))
+}
+
+
+fn is_quad(y: &FieldElement) -> bool {
+    y.sqrt().is_some().into()
+}
+
+
+// Kernel features byte with the fee field (including the fee shift bits) and the lock height,
+// from transaction kernel features (e.g. {"Plain": {"fee": 1000000}}) or from block kernel fields.
+fn features(kernel: &Value) -> Option<(u8, u64, u64)> {
+    let number = |value: &Value| value.as_u64().unwrap_or_default();
+
+    let (name, fee, lock) = match &kernel["features"] {
+        // Block kernels have the fee and the fee shift in separate fields
+        Value::String(name)   => (name.as_str(), number(&kernel["fee"]) | (number(&kernel["fee_shift"]) << 40),
+                                  number(&kernel["lock_height"])),
+        Value::Object(fields) => {
+                                     let (name, fields) = fields.iter().next()?;
+                                     let lock = if name == "NoRecentDuplicate" { "relative_height" } else { "lock_height" };
+
+                                     (name.as_str(), number(&fields["fee"]), number(&fields[lock]))
+                                 },
+        _                     => return None,
+    };
+
+    let byte = match name {
+        "Plain"             => 0,
+        "Coinbase"          => 1,
+        "HeightLocked"      => 2,
+        "NoRecentDuplicate" => 3,
+        _                   => return None,
+    };
+
+    Some((byte, fee, lock))
+}
+
+
+// Kernel signature message, blake2b hash of the features byte followed by the fee and lock fields.
+pub fn kernel_message(kernel: &Value) -> Option<[u8; 32]> {
+    let (byte, fee, lock) = features(kernel)?;
+    let mut hasher        = Blake2b::<U32>::new();
+
+    hasher.update([byte]);
+
+    match byte {
+        0 => hasher.update(fee.to_be_bytes()),
+        2 => { hasher.update(fee.to_be_bytes()); hasher.update(lock.to_be_bytes()); },
+        3 => { hasher.update(fee.to_be_bytes()); hasher.update((lock as u16).to_be_bytes()); },
+        _ => (),
+    }
+
+    Some(hasher.finalize().into())
+}
+
+
+// Verifying aggsig signature of a kernel (excess, excess_sig and features fields) against its message.
+pub fn kernel_signature(kernel: &Value) -> bool {
+    verify_signature(kernel).unwrap_or(false)
+}
+
+
+fn verify_signature(kernel: &Value) -> Option<bool> {
+    let msg    = kernel_message(kernel)?;
+    let pubkey = commitment(kernel["excess"].as_str()?)?;
+    let sig    = from_hex(kernel["excess_sig"].as_str()?)?;
+
+    if sig.len() != 64 {
+        return None;
+    }
+
+    // Compact serialization of aggsig signatures holds R.x and s in reverse byte order
+    let r_x: Vec<u8> = sig[..32].iter().rev().copied().collect();
+    let s:   Vec<u8> = sig[32..].iter().rev().copied().collect();
+
+    let r_x = Option::<FieldElement>::from(FieldElement::from_bytes(FieldBytes::from_slice(&r_x)))?;
+    let s   = Option::<Scalar>::from(Scalar::from_repr(*FieldBytes::from_slice(&s)))?;
+
+    // Public nonce has quadratic residue y
+    let r_y   = Option::<FieldElement>::from((r_x.square() * r_x + FieldElement::from(7u64)).sqrt())?.normalize();
+    let nonce = point(&r_x, &r_y)?;
+
+    let mut hasher = Sha256::new();
+
+    hasher.update(nonce.to_affine().to_encoded_point(true).as_bytes());
+    hasher.update(pubkey.to_affine().to_encoded_point(true).as_bytes());
+    hasher.update(msg);
+
+    let e = <Scalar as Reduce<U256>>::reduce_bytes(&hasher.finalize());
+
+    let (x, y) = coordinates(&(ProjectivePoint::GENERATOR * s - pubkey * e))?;
+
+    Some(is_quad(&y) && x.normalize().to_bytes() == r_x.to_bytes())
+}
+
+
+fn offset(hex: &str) -> Option<Scalar> {
+    let bytes = from_hex(hex)?;
+
+    if bytes.len() != 32 {
+        return None;
+    }
+
+    Option::<Scalar>::from(Scalar::from_repr(*FieldBytes::from_slice(&bytes)))
+}
+
+
+// Verifying block kernel sums, outputs minus inputs minus the reward are equal to the kernel excesses
+// with the block kernel offset (difference of the total kernel offsets of the block and the previous block).
+pub fn kernel_sums(block: &Value, prev_offset: &str) -> bool {
+    verify_sums(block, prev_offset).unwrap_or(false)
+}
+
+
+fn verify_sums(block: &Value, prev_offset: &str) -> Option<bool> {
+    // Reward is the overage of a block, fees are already in the coinbase output
+    let utxo_sum   = utxo_sum(block)? - generator_h() * Scalar::from(schedule::BLOCK_REWARD * 1000000000);
+    let kernel_sum = kernel_sum(block)? + ProjectivePoint::GENERATOR
+                     * (offset(block["header"]["total_kernel_offset"].as_str()?)? - offset(prev_offset)?);
+
+    Some(utxo_sum == kernel_sum)
+}
+
+
+// Sum of output commitments minus sum of input commitments (commitment strings or objects).
+fn utxo_sum(body: &Value) -> Option<ProjectivePoint> {
+    let mut sum = ProjectivePoint::IDENTITY;
+
+    for output in body["outputs"].as_array()? {
+        sum += commitment(output["commit"].as_str()?)?;
+    }
+
+    for input in body["inputs"].as_array()? {
+        sum -= commitment(input.as_str().or(input["commit"].as_str())?)?;
+    }
+
+    Some(sum)
+}
+
+
+fn kernel_sum(body: &Value) -> Option<ProjectivePoint> {
+    let mut sum = ProjectivePoint::IDENTITY;
+
+    for kernel in body["kernels"].as_array()? {
+        sum += commitment(kernel["excess"].as_str()?)?;
+    }
+
+    Some(sum)
+}
+
+
+// Verifying transaction kernel sums, outputs minus inputs plus the fees are equal to the kernel excesses
+// with the transaction offset.
+pub fn transaction_sums(tx: &Value) -> bool {
+    verify_tx_sums(tx).unwrap_or(false)
+}
+
+
+fn verify_tx_sums(tx: &Value) -> Option<bool> {
+    let mut fee = 0u64;
+
+    for kernel in tx["body"]["kernels"].as_array()? {
+        fee += KernelFeatures::from_json(&kernel["features"])?.fee();
+    }
+
+    let utxo_sum   = utxo_sum(&tx["body"])? + generator_h() * Scalar::from(fee);
+    let kernel_sum = kernel_sum(&tx["body"])? + ProjectivePoint::GENERATOR * offset(tx["offset"].as_str()?)?;
+
+    Some(utxo_sum == kernel_sum)
+}
+
+
+// Public key from its compressed serialization.
+fn public_key(hex: &str) -> Option<ProjectivePoint> {
+    let encoded = EncodedPoint::from_bytes(from_hex(hex)?).ok()?;
+
+    Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&encoded)).map(ProjectivePoint::from)
+}
+
+
+// Final kernel excess and signature of a finalized slate, from the public excesses and nonces of
+// the participants and their partial signatures (s values, big endian). The excess is serialized
+// as a commitment and the signature in compact form.
+pub fn aggregate_kernel(excesses: &[String], nonces: &[String], parts: &[Vec<u8>]) -> Option<(String, String)> {
+    let mut excess = ProjectivePoint::IDENTITY;
+    let mut nonce  = ProjectivePoint::IDENTITY;
+    let mut s      = Scalar::ZERO;
+
+    for key in excesses {
+        excess += public_key(key)?;
+    }
+
+    for key in nonces {
+        nonce += public_key(key)?;
+    }
+
+    for part in parts {
+        s += Option::<Scalar>::from(Scalar::from_repr(*FieldBytes::from_slice(part.get(..32)?)))?;
+    }
+
+    let (x, y) = coordinates(&excess)?;
+    let (r, _) = coordinates(&nonce)?;
+
+    let prefix = if is_quad(&y) { "08" } else { "09" };
+    let sig    = r.normalize().to_bytes().iter().rev().chain(s.to_bytes().iter().rev()).copied().collect::<Vec<u8>>();
+
+    Some((format!("{}{}", prefix, hex(&x.normalize().to_bytes())), hex(&sig)))
+}
+
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+// Wallet address public key, either a slatepack address (bech32 with "grin" or "tgrin" prefix)
+// or a Tor v3 onion address used by older wallets.
+fn address_key(address: &str) -> Result<VerifyingKey, anyhow::Error> {
+    let address = address.trim();
+
+    let key = if let Ok((hrp, data)) = bech32::decode(address) {
+        if hrp != Hrp::parse_unchecked("grin") && hrp != Hrp::parse_unchecked("tgrin") {
+            bail!("unknown address prefix {}", hrp);
+        }
+
+        data
+    } else {
+        let onion = address.trim_start_matches("http://").trim_end_matches('/').trim_end_matches(".onion");
+        let data  = base32(onion).ok_or_else(|| anyhow!("bad address {}", address))?;
+
+        // Public key, checksum and version 3
+        if data.len() != 35 || data[34] != 3 {
+            bail!("bad address {}", address);
+        }
+
+        let mut hasher = Sha3_256::new();
+
+        hasher.update(b".onion checksum");
+        hasher.update(&data[..32]);
+        hasher.update([3]);
+
+        if hasher.finalize()[..2] != data[32..34] {
+            bail!("bad address checksum {}", address);
+        }
+
+        data[..32].to_vec()
+    };
+
+    let key: [u8; 32] = key.try_into().map_err(|_| anyhow!("bad address length {}", address))?;
+
+    VerifyingKey::from_bytes(&key).map_err(|_| anyhow!("bad address key {}", address))
+}
+
+
+// Decoding RFC 4648 base32 (no padding), case insensitive.
+fn base32(data: &str) -> Option<Vec<u8>> {
+    let mut bytes  = Vec::new();
+    let mut buffer = 0u64;
+    let mut bits   = 0;
+
+    for c in data.to_ascii_lowercase().chars() {
+        let value = match c {
+            'a'..='z' => c as u64 - 'a' as u64,
+            '2'..='7' => c as u64 - '2' as u64 + 26,
+            _         => return None,
+        };
+
+        buffer = (buffer << 5) | value;
+        bits  += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+
+    Some(bytes)
+}
+
+
+fn signature(hex: &str) -> Result<Signature, anyhow::Error> {
+    let bytes: [u8; 64] = from_hex(hex.trim()).and_then(|b| b.try_into().ok())
+                          .ok_or_else(|| anyhow!("bad signature {}", hex))?;
+
+    Ok(Signature::from_bytes(&bytes))
+}
+
+
+// Verifying a wallet payment proof (export_proof JSON). Both signatures are over the amount (u64 big endian),
+// the kernel excess and the sender address public key. The kernel is looked up on chain, at the height if given.
+pub async fn payment_proof(net: &Network, proof: &str, height: Option<u64>) -> Result<PaymentProof, anyhow::Error> {
+    let proof: Value = serde_json::from_str(proof).map_err(|e| anyhow!("bad payment proof: {}", e))?;
+
+    let amount = match &proof["amount"] {
+        Value::String(amount) => amount.parse::<u64>().ok(),
+        amount                => amount.as_u64(),
+    };
+    let amount    = amount.ok_or_else(|| anyhow!("bad amount"))?;
+    let excess    = proof["excess"].as_str().ok_or_else(|| anyhow!("missing excess"))?.trim().to_lowercase();
+    let sender    = proof["sender_address"].as_str().ok_or_else(|| anyhow!("missing sender_address"))?;
+    let recipient = proof["recipient_address"].as_str().ok_or_else(|| anyhow!("missing recipient_address"))?;
+
+    let commit = from_hex(&excess).filter(|c| c.len() == 33).ok_or_else(|| anyhow!("bad excess {}", excess))?;
+    let sender_key    = address_key(sender)?;
+    let recipient_key = address_key(recipient)?;
+    let sender_sig    = signature(proof["sender_sig"].as_str().ok_or_else(|| anyhow!("missing sender_sig"))?)?;
+    let recipient_sig = signature(proof["recipient_sig"].as_str().ok_or_else(|| anyhow!("missing recipient_sig"))?)?;
+
+    let msg = [&amount.to_be_bytes()[..], &commit[..], sender_key.as_bytes()].concat();
+
+    let mut result = PaymentProof {
+        amount,
+        excess,
+        sender:        sender.trim().to_string(),
+        recipient:     recipient.trim().to_string(),
+        sender_sig:    sender_key.verify(&msg, &sender_sig).is_ok(),
+        recipient_sig: recipient_key.verify(&msg, &recipient_sig).is_ok(),
+        height:        None,
+        confirmations: 0,
+        verified:      false,
+    };
+
+    let params = match height {
+        Some(height) => format!("[\"{}\", {}, {}]", result.excess, height, height),
+        None         => format!("[\"{}\", null, null]", result.excess),
+    };
+    let resp   = requests::call(net, "get_kernel", &params, "1", "foreign").await?;
+
+    if let Some(height) = resp["result"]["Ok"]["height"].as_u64() {
+        if let Ok(tip) = net.dash.lock().unwrap().height.parse::<u64>() {
+            result.confirmations = tip.saturating_sub(height) + 1;
+        }
+
+        result.height = Some(height);
+    }
+
+    result.verified = result.sender_sig && result.recipient_sig && result.height.is_some();
+
+    Ok(result)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Kernels and block signed with the Grin aggsig scheme by an independent reference implementation.
+    const EXCESS:     &str = "0923dc8c9a4452589f34679531ff9bde2ada111d0aee11ffd99eb850f5ca6f024d";
+    const EXCESS_SIG: &str = "b59afa4d24078384f6e2337d7e098811718c3919f9a5c3ce45aa12efde477658cf0fb9a0d4e8439758fbfef78bc14a2f36b06ce1ffc6f4cba6b5f23274ca9b7e";
+
+    #[test]
+    fn signatures() {
+        let kernel = json!({"features": {"Plain": {"fee": 7000000}}, "excess": EXCESS, "excess_sig": EXCESS_SIG});
+
+        assert!(kernel_signature(&kernel));
+
+        // Same kernel in the block (printable) form
+        assert!(kernel_signature(&json!({"features": "Plain", "fee": 7000000, "fee_shift": 0, "lock_height": 0,
+                                         "excess": EXCESS, "excess_sig": EXCESS_SIG})));
+
+        // Other fee, tampered signature or excess
+        assert!(!kernel_signature(&json!({"features": {"Plain": {"fee": 7000001}}, "excess": EXCESS, "excess_sig": EXCESS_SIG})));
+        assert!(!kernel_signature(&json!({"features": {"Plain": {"fee": 7000000}}, "excess": EXCESS,
+                                          "excess_sig": EXCESS_SIG.replacen('b', "c", 1)})));
+        assert!(!kernel_signature(&json!({"features": {"Plain": {"fee": 7000000}}, "excess": EXCESS.replacen('9', "8", 1),
+                                          "excess_sig": EXCESS_SIG})));
+    }
+
+    #[test]
+    fn fee_shift_and_lock_height() {
+        let excess = "094deb5e4bf849790657361d0559b96d9277fdfcf02f6f78f021e834b7282c9db8";
+        let sig    = "6f624804563fe8cd88f40be8c87454f6e2f39553ecc0dff7b3e6b7c037508e739f855bc85a40b9f4877856262ca964392c4b0b07b5cbdf3b48250de2f60e99d1";
+
+        // Fee field with the fee shift bits is signed
+        assert!(kernel_signature(&json!({"features": {"Plain": {"fee": (1u64 << 40) | 7000000}}, "excess": excess, "excess_sig": sig})));
+        assert!(kernel_signature(&json!({"features": "Plain", "fee": 7000000, "fee_shift": 1, "lock_height": 0,
+                                         "excess": excess, "excess_sig": sig})));
+        assert!(!kernel_signature(&json!({"features": "Plain", "fee": 7000000, "fee_shift": 0, "lock_height": 0,
+                                          "excess": excess, "excess_sig": sig})));
+
+        let excess = "09556a282fe92ccca8a22db8e78ff5cd9154f0e1e22d0511e17d256d1c12637362";
+        let sig    = "da2609e7b87c19df113f7d4ce2539a0cf2012a43a8efa82ff8bce8385bd462791142cef710590f06b368ebeb984a870b6bf9d93fbc0e07463e9cf99063406c4d";
+
+        assert!(kernel_signature(&json!({"features": {"HeightLocked": {"fee": 8000000, "lock_height": 2500000}},
+                                         "excess": excess, "excess_sig": sig})));
+        assert!(kernel_signature(&json!({"features": "HeightLocked", "fee": 8000000, "fee_shift": 0, "lock_height": 2500000,
+                                         "excess": excess, "excess_sig": sig})));
+        assert!(!kernel_signature(&json!({"features": "HeightLocked", "fee": 8000000, "fee_shift": 0, "lock_height": 2500001,
+                                          "excess": excess, "excess_sig": sig})));
+    }
+
+    #[test]
+    fn block_sums() {
+        let block = json!({
+            "header":  {"total_kernel_offset": "0000000000000000000000000000000000000000000000000000000000000435"},
+            "inputs":  ["09d904d175b78cb57ff6d407923cdf3d53ee6b44bad099541133c8d1b8fe5c4cfb"],
+            "outputs": [{"commit": "089f27acaed4406116320dd07b914723939a3b986fa47c10c960adad8022ad0f92"},
+                        {"commit": "08e122b437df8cb882b50c512d556493da1575781d7e8bd73da1fe533cea4ffa6e"}],
+            "kernels": [{"excess": "091697ffa6fd9de627c077e3d2fe541084ce13300b0bec1146f95ae57f0d0bd6a5"}],
+        });
+        let prev = "00000000000000000000000000000000000000000000000000000000000003e8";
+
+        assert!(kernel_sums(&block, prev));
+        assert!(!kernel_sums(&block, "00000000000000000000000000000000000000000000000000000000000003e9"));
+        assert!(!kernel_sums(&block, "not an offset"));
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        assert_eq!(from_hex("00ff10"), Some(vec![0, 255, 16]));
+        assert_eq!(hex(&[0, 255, 16]), "00ff10");
+        assert_eq!(from_hex("0ff"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+}
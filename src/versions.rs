@@ -0,0 +1,197 @@
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::data::{ConnectedNode, VersionCount};
+
+
+// Capability bits.
+// https://github.com/mimblewimble/grin/blob/master/p2p/src/types.rs
+pub const CAPABILITIES: [(u32, &str); 8] = [
+    (1 << 0, "header_hist"),
+    (1 << 1, "txhashset_hist"),
+    (1 << 2, "peer_list"),
+    (1 << 3, "tx_kernel_hash"),
+    (1 << 4, "tor_address"),
+    (1 << 5, "block_hist"),
+    (1 << 6, "pibd_hist"),
+    (1 << 7, "pibd_hist_1"),
+];
+
+// Number of user agents shown on the adoption chart, the rest are summed up as "Other".
+const TOP_USER_AGENTS: usize = 6;
+
+
+// Counting nodes by user agent, protocol version and capability.
+pub fn snapshot(nodes: &[ConnectedNode], date: &str) -> Vec<VersionCount> {
+    let mut counts: BTreeMap<(String, String), u32> = BTreeMap::new();
+
+    for node in nodes.iter() {
+        let protocol = if node.version > 0 { node.version.to_string() } else { "unknown".to_string() };
+
+        *counts.entry(("user_agent".to_string(), node.user_agent.clone())).or_insert(0) += 1;
+        *counts.entry(("protocol".to_string(), protocol)).or_insert(0) += 1;
+
+        for (bit, name) in CAPABILITIES.iter() {
+            if node.bits & bit != 0 {
+                *counts.entry(("capability".to_string(), name.to_string())).or_insert(0) += 1;
+            }
+        }
+    }
+
+    counts.insert(("total".to_string(), "nodes".to_string()), nodes.len() as u32);
+
+    counts.into_iter()
+          .map(|((kind, name), count)| VersionCount { date: date.to_string(), kind, name, count })
+          .collect()
+}
+
+
+// Snapshots grouped by date, for the JSON API.
+pub fn to_json(history: &[VersionCount]) -> Value {
+    let mut days: BTreeMap<String, Value> = BTreeMap::new();
+
+    for c in history.iter() {
+        let day = days.entry(c.date.clone()).or_insert(json!({
+            "date":         c.date,
+            "total":        0,
+            "user_agents":  {},
+            "protocols":    {},
+            "capabilities": {},
+        }));
+
+        match c.kind.as_str() {
+            "total"      => day["total"] = json!(c.count),
+            "user_agent" => day["user_agents"][&c.name] = json!(c.count),
+            "protocol"   => day["protocols"][&c.name] = json!(c.count),
+            "capability" => day["capabilities"][&c.name] = json!(c.count),
+            _            => {},
+        }
+    }
+
+    json!(days.into_values().collect::<Vec<Value>>())
+}
+
+
+// Chart data: dates and the share of nodes (percent) per name of the given kind.
+// Returned as JSON to be embedded in a script tag (empty if there is no data), user agents are
+// reported by peers, so "<", ">" and "/" are escaped.
+pub fn chart(history: &[VersionCount], kind: &str) -> String {
+    let dates: Vec<String> = history.iter().map(|c| c.date.clone()).collect::<BTreeSet<String>>()
+                                    .into_iter().collect();
+
+    if dates.is_empty() {
+        return String::new();
+    }
+
+    let total = |date: &String| history.iter().find(|c| c.date == *date && c.kind == "total").map(|c| c.count).unwrap_or(0);
+
+    // Names ordered by the latest snapshot
+    let mut names: Vec<(String, u32)> = history.iter().filter(|c| c.kind == kind && Some(&c.date) == dates.last())
+                                               .map(|c| (c.name.clone(), c.count)).collect();
+
+    names.sort_by_key(|n| Reverse(n.1));
+
+    for c in history.iter().filter(|c| c.kind == kind) {
+        if !names.iter().any(|n| n.0 == c.name) {
+            names.push((c.name.clone(), 0));
+        }
+    }
+
+    let limit = if kind == "user_agent" { TOP_USER_AGENTS } else { names.len() };
+    let mut datasets = Vec::new();
+
+    let share = |date: &String, filter: &dyn Fn(&VersionCount) -> bool| -> Value {
+        let count: u32 = history.iter().filter(|c| c.date == *date && c.kind == kind && filter(c)).map(|c| c.count).sum();
+
+        match total(date) {
+            0 => Value::Null,
+            t => json!((count as f64 * 1000.0 / t as f64).round() / 10.0),
+        }
+    };
+
+    for (name, _) in names.iter().take(limit) {
+        datasets.push(json!({
+            "label": name,
+            "data":  dates.iter().map(|d| share(d, &|c: &VersionCount| c.name == *name)).collect::<Vec<Value>>(),
+        }));
+    }
+
+    if names.len() > limit {
+        let top: Vec<&String> = names.iter().take(limit).map(|n| &n.0).collect();
+
+        datasets.push(json!({
+            "label": "Other",
+            "data":  dates.iter().map(|d| share(d, &|c: &VersionCount| !top.contains(&&c.name))).collect::<Vec<Value>>(),
+        }));
+    }
+
+    json!({
+        "labels":   dates,
+        "datasets": datasets,
+    }).to_string().replace('<', "\\u003c").replace('>', "\\u003e").replace('/', "\\/")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(user_agent: &str, version: u32, bits: u32) -> ConnectedNode {
+        let mut node = ConnectedNode::new();
+
+        node.user_agent = user_agent.to_string();
+        node.version    = version;
+        node.bits       = bits;
+
+        node
+    }
+
+    fn count(counts: &[VersionCount], kind: &str, name: &str) -> Option<u32> {
+        counts.iter().find(|c| c.kind == kind && c.name == name).map(|c| c.count)
+    }
+
+    #[test]
+    fn snapshot_counts() {
+        let nodes  = [node("MW/Grin 5.3.3", 1000, 0b101), node("MW/Grin 5.3.3", 1000, 0b1), node("MW/Grin 5.2.1", 0, 0)];
+        let counts = snapshot(&nodes, "2024-05-01");
+
+        assert!(counts.iter().all(|c| c.date == "2024-05-01"));
+        assert_eq!(count(&counts, "total", "nodes"), Some(3));
+        assert_eq!(count(&counts, "user_agent", "MW/Grin 5.3.3"), Some(2));
+        assert_eq!(count(&counts, "user_agent", "MW/Grin 5.2.1"), Some(1));
+        assert_eq!(count(&counts, "protocol", "1000"), Some(2));
+        assert_eq!(count(&counts, "protocol", "unknown"), Some(1));
+        assert_eq!(count(&counts, "capability", "header_hist"), Some(2));
+        assert_eq!(count(&counts, "capability", "peer_list"), Some(1));
+        assert_eq!(count(&counts, "capability", "block_hist"), None);
+    }
+
+    #[test]
+    fn chart_shares() {
+        let mut history = snapshot(&[node("A", 1000, 0), node("B", 1000, 0), node("B", 1000, 0), node("C", 1000, 0)], "2024-05-01");
+
+        history.extend(snapshot(&[node("A", 1000, 0)], "2024-05-02"));
+
+        let chart: Value = serde_json::from_str(&chart(&history, "user_agent")).unwrap();
+
+        assert_eq!(chart["labels"], json!(["2024-05-01", "2024-05-02"]));
+        // Ordered by the latest snapshot, then names missing from it
+        assert_eq!(chart["datasets"][0], json!({"label": "A", "data": [25.0, 100.0]}));
+        assert_eq!(chart["datasets"][1]["data"], json!([50.0, 0.0]));
+        assert_eq!(chart["datasets"].as_array().unwrap().len(), 3);
+        assert_eq!(super::chart(&[], "user_agent"), "");
+    }
+
+    #[test]
+    fn chart_escaping() {
+        let history = snapshot(&[node("</script><script>alert(1)</script>", 1000, 0)], "2024-05-01");
+        let chart   = chart(&history, "user_agent");
+
+        assert!(!chart.contains('<') && !chart.contains('>') && !chart.contains("</"));
+
+        let chart: Value = serde_json::from_str(&chart).unwrap();
+
+        assert_eq!(chart["datasets"][0]["label"], "</script><script>alert(1)</script>");
+    }
+}
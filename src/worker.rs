@@ -1,42 +1,42 @@
 use chrono::Utc;
 use std::sync::{Arc, Mutex};
 
-use crate::data::Block;
-use crate::data::Dashboard;
-use crate::data::NetStats;
-use crate::data::Statistics;
-use crate::data::Transactions;
+use crate::crawler;
+use crate::data::ApiKey;
 use crate::database;
 use crate::exconfig::CONFIG;
+use crate::mempool;
+use crate::networks::Network;
+use crate::prices;
 use crate::requests;
+use crate::versions;
 
 
 // Collecting main data.
-pub async fn data(dash: Arc<Mutex<Dashboard>>, blocks: Arc<Mutex<Vec<Block>>>,
-                  txns: Arc<Mutex<Transactions>>, stats: Arc<Mutex<Statistics>>,
-                  netstats: Arc<Mutex<NetStats>>) -> Result<(), anyhow::Error> {
-    let _ = requests::get_status(dash.clone()).await?;
-    let _ = requests::get_mempool(dash.clone()).await?;
-    let _ = requests::get_connected_peers(dash.clone(), stats.clone(), netstats.clone()).await?;
-    let _ = requests::get_market(dash.clone()).await?;
-    let _ = requests::get_disk_usage(dash.clone())?;
-    let _ = requests::get_mining_stats(dash.clone()).await?;
-    let _ = requests::get_recent_blocks(dash.clone(), blocks.clone()).await?;
-    let _ = requests::get_txn_stats(dash.clone(), txns.clone()).await?;
-    let _ = requests::get_pubnodes_stats(netstats.clone()).await?;
+pub async fn data(net: &Network) -> Result<(), anyhow::Error> {
+    let _ = requests::check_upstreams(net).await?;
+    let _ = requests::get_status(net).await?;
+    let _ = requests::get_mempool(net).await?;
+    let _ = requests::get_connected_peers(net).await?;
+    let _ = requests::get_market(net).await?;
+    let _ = requests::get_disk_usage(net)?;
+    let _ = requests::get_mining_stats(net).await?;
+    let _ = requests::get_recent_blocks(net).await?;
+    let _ = requests::get_txn_stats(net).await?;
+    let _ = requests::get_pubnodes_stats(net).await?;
+    mempool::record(net).await?;
 
     Ok(())
 }
 
 // Collecting statistics.
-pub async fn stats(dash: Arc<Mutex<Dashboard>>, txns: Arc<Mutex<Transactions>>, stats: Arc<Mutex<Statistics>>, netstats: Arc<Mutex<NetStats>>) -> Result<(), anyhow::Error> {
+pub async fn stats(net: &Network) -> Result<(), anyhow::Error> {
 
-    let _ = requests::get_unspent_outputs(dash.clone()).await?;
-    let _ = requests::get_reachable_nodes(netstats.clone()).await?;
+    let _ = requests::get_unspent_outputs(net).await?;
 
-    let mut stats = stats.lock().unwrap();
-    let dash      = dash.lock().unwrap();
-    let txns      = txns.lock().unwrap();
+    let mut stats = net.stats.lock().unwrap();
+    let dash      = net.dash.lock().unwrap();
+    let txns      = net.txns.lock().unwrap();
 
     stats.date.push(format!("\"{}\"", Utc::now().format("%d-%m-%Y")));
     stats.hashrate.push(dash.hashrate_kgs.clone());
@@ -44,6 +44,17 @@ pub async fn stats(dash: Arc<Mutex<Dashboard>>, txns: Arc<Mutex<Transactions>>,
     stats.fees.push(txns.fees_24h.clone());
     stats.utxos.push(dash.utxo_count.clone());
 
+    // Node versions snapshot, crawled network if it was already handshaked or connected peers otherwise
+    let nodes = {
+        let nstats = net.netstats.lock().unwrap();
+        if nstats.crawled > 0 && !nstats.reach_nodes.is_empty() { nstats.reach_nodes.clone() } else { nstats.conn_nodes.clone() }
+    };
+    let today    = Utc::now().format("%Y-%m-%d").to_string();
+    let snapshot = versions::snapshot(&nodes, &today);
+
+    stats.versions.retain(|c| c.date != today);
+    stats.versions.extend(snapshot.clone());
+
     let mut kernel_count = 0;
 
     if dash.kernel_mmr_size.is_empty() == false {
@@ -51,17 +62,41 @@ pub async fn stats(dash: Arc<Mutex<Dashboard>>, txns: Arc<Mutex<Transactions>>,
         stats.kernels.push(kernel_count.to_string());
     }
 
-    if CONFIG.database.is_empty() == false {
+    if net.config.database.is_empty() == false {
         // Open the database
-        let conn = database::open_db_connection(&CONFIG.database).expect("failed to open database");
+        let conn = database::open_db_connection(&net.config.database).expect("failed to open database");
 
         //Insert new data into the database
         conn.execute(
             "INSERT OR IGNORE INTO statistics (date, hashrate, txns, fees, utxos, kernels) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             (&format!("\"{}\"", Utc::now().format("%d-%m-%Y")), &dash.hashrate_kgs.clone(), &txns.period_24h.clone(), &txns.fees_24h.clone(), &dash.utxo_count.clone(), &kernel_count.to_string()),
         )?;
+
+        database::save_versions(&conn, &today, &snapshot)?;
+
+        drop(stats);
+        prices::load_history(net, &conn)?;
+        crawler::load_history(net, &conn)?;
     }
 
     Ok(())
 }
 
+
+
+// Saving API keys usage.
+pub fn api_keys(keys: Arc<Mutex<Vec<ApiKey>>>) -> Result<(), anyhow::Error> {
+    let keys = keys.lock().unwrap().clone();
+
+    if CONFIG.database.is_empty() || keys.is_empty() {
+        return Ok(());
+    }
+
+    let conn = database::open_db_connection(&CONFIG.database)?;
+
+    for key in keys.iter() {
+        database::update_api_key_usage(&conn, key)?;
+    }
+
+    Ok(())
+}